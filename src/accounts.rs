@@ -0,0 +1,202 @@
+use crate::types::api::UserResponse;
+use crate::{cassette, config};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static SELECTED_ACCOUNT: OnceLock<Option<String>> = OnceLock::new();
+
+/// A user that has logged in with the CLI. Only identifying information is
+/// kept here. The credential for each account lives in the credential store.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+    pub email: Option<String>,
+}
+
+impl From<UserResponse> for Account {
+    fn from(user: UserResponse) -> Self {
+        Account {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+        }
+    }
+}
+
+impl Account {
+    /// Whether the account can be referred to by the provided value, which
+    /// may be its ID, email or name.
+    fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+
+        self.id == query
+            || self.name.eq_ignore_ascii_case(query)
+            || self
+                .email
+                .as_ref()
+                .is_some_and(|email| email.eq_ignore_ascii_case(query))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct ProfileAccounts {
+    active: Option<String>,
+    accounts: Vec<Account>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct AccountsFile {
+    profiles: BTreeMap<String, ProfileAccounts>,
+}
+
+/// Sets the account provided with the `--account` flag, which takes
+/// precedence over the active account for this invocation.
+pub fn init_selected_account(account_arg: Option<String>) {
+    SELECTED_ACCOUNT.get_or_init(|| account_arg);
+}
+
+/// The key the credential for the account is stored under in the credential
+/// store. Accounts are scoped to the active profile.
+pub fn credential_key(account_id: &str) -> String {
+    format!(
+        "{}:{}",
+        config::active_profile().credential_key(),
+        account_id
+    )
+}
+
+/// All accounts for the active profile, along with the ID of the active one.
+pub fn list_accounts() -> Result<(Vec<Account>, Option<String>), Box<dyn Error>>
+{
+    let mut file = load()?;
+    let profile_accounts = file
+        .profiles
+        .remove(&config::active_profile().storage_name())
+        .unwrap_or_default();
+
+    Ok((profile_accounts.accounts, profile_accounts.active))
+}
+
+pub fn find_account(query: &str) -> Result<Account, Box<dyn Error>> {
+    let (accounts, _) = list_accounts()?;
+    let matching: Vec<Account> = accounts
+        .into_iter()
+        .filter(|account| account.matches(query))
+        .collect();
+
+    match matching.len() {
+        0 => Err(format!(
+            "No account matching \"{}\". Run `parra auth list` to see the available accounts.",
+            query
+        )
+        .into()),
+        1 => Ok(matching.into_iter().next().unwrap()),
+        _ => Err(format!(
+            "More than one account matches \"{}\". Use the account ID instead.",
+            query
+        )
+        .into()),
+    }
+}
+
+/// The account to use for this invocation. This is the one provided with
+/// `--account` if there is one, otherwise the active account.
+pub fn current_account() -> Result<Option<Account>, Box<dyn Error>> {
+    if let Some(Some(query)) = SELECTED_ACCOUNT.get() {
+        return Ok(Some(find_account(query)?));
+    }
+
+    let (accounts, active) = list_accounts()?;
+
+    Ok(active.and_then(|active| {
+        accounts.into_iter().find(|account| account.id == active)
+    }))
+}
+
+/// Adds or updates the account and makes it the active one.
+pub fn add_account(account: Account) -> Result<(), Box<dyn Error>> {
+    update_profile_accounts(|profile_accounts| {
+        profile_accounts
+            .accounts
+            .retain(|existing| existing.id != account.id);
+
+        profile_accounts.active = Some(account.id.clone());
+        profile_accounts.accounts.push(account);
+    })
+}
+
+/// Removes the account. If it was the active account, the first remaining
+/// account becomes active.
+pub fn remove_account(account_id: &str) -> Result<(), Box<dyn Error>> {
+    update_profile_accounts(|profile_accounts| {
+        profile_accounts
+            .accounts
+            .retain(|existing| existing.id != account_id);
+
+        if profile_accounts.active.as_deref() == Some(account_id) {
+            profile_accounts.active = profile_accounts
+                .accounts
+                .first()
+                .map(|account| account.id.clone());
+        }
+    })
+}
+
+pub fn set_active_account(account_id: &str) -> Result<(), Box<dyn Error>> {
+    update_profile_accounts(|profile_accounts| {
+        profile_accounts.active = Some(account_id.to_string());
+    })
+}
+
+fn update_profile_accounts<F: FnOnce(&mut ProfileAccounts)>(
+    update: F,
+) -> Result<(), Box<dyn Error>> {
+    let mut file = load()?;
+    let profile_accounts = file
+        .profiles
+        .entry(config::active_profile().storage_name())
+        .or_default();
+
+    update(profile_accounts);
+
+    save(&file)
+}
+
+fn accounts_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config::config_dir()?.join("accounts.json"))
+}
+
+fn load() -> Result<AccountsFile, Box<dyn Error>> {
+    let path = accounts_path()?;
+
+    if !path.exists() {
+        return Ok(AccountsFile::default());
+    }
+
+    let data = fs::read_to_string(&path)?;
+
+    Ok(serde_json::from_str::<AccountsFile>(&data)?)
+}
+
+fn save(file: &AccountsFile) -> Result<(), Box<dyn Error>> {
+    // Replayed logins are for accounts with redacted tokens, which shouldn't
+    // replace the real ones.
+    if cassette::is_replaying() {
+        return Ok(());
+    }
+
+    let path = accounts_path()?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, serde_json::to_string_pretty(file)?)?;
+
+    Ok(())
+}
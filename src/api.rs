@@ -1,187 +1,642 @@
 use crate::{
-    auth,
+    api_error::ApiError,
+    auth, config, http_client, logging,
     types::{
         api::{
-            ApplicationCollectionResponse, ApplicationRequest,
-            ApplicationResponse, ApplicationType, AuthorizedUser,
-            TenantRequest, TenantResponse, UserInfoResponse,
+            ApplicationRequest, ApplicationResponse, ApplicationType,
+            AuthorizedUser, CollectionPage, CollectionResponse, TenantRequest,
+            TenantResponse, UpdateApplicationRequest, UserInfoResponse,
+            UserResponse,
         },
         auth::Credental,
     },
 };
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use std::error::Error;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+const PAGE_SIZE: u64 = 100;
+/// Listing stops with an error after this many pages, so that a server that
+/// never reports the end of a collection can't keep the CLI fetching forever.
+const MAX_PAGES: u64 = 1_000;
+
+/// An authenticated connection to the Parra API that lasts for a single
+/// command. Authentication happens once when the session is created, and if
+/// the API rejects the access token part way through, it is refreshed and the
+/// request is sent again.
+pub struct ApiSession {
+    authorized_user: Mutex<AuthorizedUser>,
+    client: &'static reqwest::Client,
+}
+
+impl ApiSession {
+    /// Authenticates, logging in first if necessary, and fetches the user the
+    /// credential belongs to.
+    pub async fn new() -> Result<Self, Box<dyn Error>> {
+        let credential = auth::perform_device_authentication().await?;
+        let user = get_user_info(&credential).await?;
+
+        Ok(ApiSession {
+            authorized_user: Mutex::new(AuthorizedUser { credential, user }),
+            client: http_client::shared_client(),
+        })
+    }
+
+    pub fn authorized_user(&self) -> AuthorizedUser {
+        self.authorized_user.lock().unwrap().clone()
+    }
+
+    pub fn user_id(&self) -> String {
+        self.authorized_user.lock().unwrap().user.id.clone()
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: Vec<(&str, &str)>,
+    ) -> Result<T, Box<dyn Error>> {
+        self.send(|credential| {
+            perform_get_request(self.client, credential, endpoint, &query)
+        })
+        .await
+    }
+
+    async fn send_with_body<T: DeserializeOwned, U: Serialize>(
+        &self,
+        endpoint: &str,
+        method: reqwest::Method,
+        body: U,
+    ) -> Result<T, Box<dyn Error>> {
+        // Requests that make changes are refused when the server has said this
+        // version is too old, since it may send them in a form the server no
+        // longer accepts.
+        if let Some(minimum) = http_client::unsupported_version() {
+            return Err(Box::new(ApiError::UnsupportedVersion {
+                minimum: minimum.to_string(),
+            }));
+        }
+
+        self.send(|credential| {
+            perform_request_with_body(
+                self.client,
+                credential,
+                endpoint,
+                method.clone(),
+                &body,
+            )
+        })
+        .await
+    }
+
+    /// Performs the request with the current credential. If it is rejected as
+    /// unauthorized, the credential is refreshed once and the request retried.
+    async fn send<T, F, Fut>(&self, perform: F) -> Result<T, Box<dyn Error>>
+    where
+        F: Fn(Credental) -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn Error>>>,
+    {
+        let authorized_user = self.authorized_user();
+
+        let error = match perform(authorized_user.credential.clone()).await {
+            Err(error) if is_unauthorized(error.as_ref()) => error,
+            result => return result,
+        };
+
+        let credential = match auth::refresh_rejected_credential(
+            &authorized_user.user.id,
+            &authorized_user.credential,
+        )
+        .await?
+        {
+            Some(credential) => credential,
+            None => return Err(error),
+        };
+
+        self.authorized_user.lock().unwrap().credential = credential.clone();
+
+        perform(credential).await
+    }
+}
+
+fn is_unauthorized(error: &(dyn Error + 'static)) -> bool {
+    matches!(
+        error.downcast_ref::<ApiError>(),
+        Some(ApiError::Unauthorized { .. })
+    )
+}
+
+/// Fetches a collection one page at a time, so that large collections are
+/// never requested in one go. Pages are requested with the cursor from the
+/// previous page when the endpoint provides one, and by offset otherwise.
+pub struct Paginator<'a, T> {
+    session: &'a ApiSession,
+    endpoint: String,
+    pages: PageState,
+    item_type: PhantomData<T>,
+}
+
+/// Where the next page starts, and whether the collection has been read to
+/// the end.
+struct PageState {
+    page_size: u64,
+    skip: u64,
+    cursor: Option<String>,
+    pages_fetched: u64,
+    /// The ID of the first item of the previous page, to notice a server that
+    /// ignores the offset and returns the same page again.
+    first_id: Option<Value>,
+    finished: bool,
+}
+
+impl<'a, T: DeserializeOwned> Paginator<'a, T> {
+    fn new(session: &'a ApiSession, endpoint: String) -> Self {
+        Paginator {
+            session,
+            endpoint,
+            pages: PageState::new(PAGE_SIZE),
+            item_type: PhantomData,
+        }
+    }
+
+    /// The next page of items, or `None` once every page has been fetched.
+    pub async fn next_page(
+        &mut self,
+    ) -> Result<Option<Vec<T>>, Box<dyn Error>> {
+        if self.pages.finished {
+            return Ok(None);
+        }
+
+        let query = self.pages.query();
+        let page: CollectionPage<Value> = self
+            .session
+            .get(
+                &self.endpoint,
+                query
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_str()))
+                    .collect(),
+            )
+            .await?;
+
+        match self.pages.advance(page.into_response())? {
+            Some(data) => Ok(Some(
+                data.into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches every remaining page and returns all of their items.
+    pub async fn collect_all(mut self) -> Result<Vec<T>, Box<dyn Error>> {
+        let mut items = vec![];
+
+        while let Some(page) = self.next_page().await? {
+            items.extend(page);
+        }
+
+        Ok(items)
+    }
+}
+
+impl PageState {
+    fn new(page_size: u64) -> Self {
+        PageState {
+            page_size,
+            skip: 0,
+            cursor: None,
+            pages_fetched: 0,
+            first_id: None,
+            finished: false,
+        }
+    }
+
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![("$top", self.page_size.to_string())];
+
+        match &self.cursor {
+            Some(cursor) => query.push(("$cursor", cursor.clone())),
+            None => query.push(("$skip", self.skip.to_string())),
+        }
+
+        query
+    }
+
+    /// Moves past a page that was just fetched, returning its items unless it
+    /// is empty or a repeat of the previous page.
+    fn advance(
+        &mut self,
+        response: CollectionResponse<Value>,
+    ) -> Result<Option<Vec<Value>>, Box<dyn Error>> {
+        let count = response.data.len() as u64;
+        let first_id = response
+            .data
+            .first()
+            .and_then(|item| item.get("id").cloned());
+
+        if count == 0 || (first_id.is_some() && first_id == self.first_id) {
+            self.finished = true;
+
+            return Ok(None);
+        }
+
+        self.pages_fetched += 1;
+
+        if self.pages_fetched > MAX_PAGES {
+            self.finished = true;
+
+            return Err(format!(
+                "Stopped listing after {} pages. The collection may be too \
+                 large to list, or the server may be returning the same \
+                 items again.",
+                MAX_PAGES
+            )
+            .into());
+        }
+
+        self.skip += count;
+        self.cursor = response.next_cursor;
+        self.first_id = first_id;
+        self.finished = match (&self.cursor, response.total_count) {
+            (Some(_), _) => false,
+            (None, Some(total_count)) => self.skip >= total_count,
+            (None, None) => {
+                count < response.page_size.unwrap_or(self.page_size)
+            }
+        };
+
+        Ok(Some(response.data))
+    }
+}
 
 pub async fn get_tenant(
+    session: &ApiSession,
     tenant_id: &str,
 ) -> Result<TenantResponse, Box<dyn Error>> {
     // get-tenant-by-id
 
-    let authorized_user = ensure_auth().await?;
-
     let endpoint = format!("/tenants/{}", tenant_id);
-    let response: TenantResponse =
-        perform_get_request(&authorized_user.credential, &endpoint, vec![])
-            .await?;
+    let response: TenantResponse = session.get(&endpoint, vec![]).await?;
 
     Ok(response)
 }
 
-pub async fn get_tenants() -> Result<Vec<TenantResponse>, Box<dyn Error>> {
+pub fn paginate_tenants(session: &ApiSession) -> Paginator<'_, TenantResponse> {
     // get-tenants-for-user-by-id
 
-    let authorized_user = ensure_auth().await?;
-
-    let endpoint = format!("/users/{}/tenants", authorized_user.user.id);
-    let tenants: Vec<TenantResponse> =
-        perform_get_request(&authorized_user.credential, &endpoint, vec![])
-            .await?;
+    let endpoint = format!("/users/{}/tenants", session.user_id());
 
-    Ok(tenants)
+    Paginator::new(session, endpoint)
 }
 
 pub async fn create_tenant(
+    session: &ApiSession,
     name: &str,
+    is_test: bool,
 ) -> Result<TenantResponse, Box<dyn Error>> {
     // create-tenant-for-user-by-id
-    let authorized_user = ensure_auth().await?;
 
-    let endpoint = format!("/users/{}/tenants", authorized_user.user.id);
+    let endpoint = format!("/users/{}/tenants", session.user_id());
     let body = TenantRequest {
         name: name.to_string(),
-        is_test: false,
+        is_test,
     };
 
-    let response: TenantResponse = perform_request_with_body(
-        &authorized_user.credential,
-        &endpoint,
-        reqwest::Method::POST,
-        body,
-    )
-    .await?;
+    let response: TenantResponse = session
+        .send_with_body(&endpoint, reqwest::Method::POST, body)
+        .await?;
 
     Ok(response)
 }
 
-pub async fn paginate_applications(
+pub fn paginate_applications<'a>(
+    session: &'a ApiSession,
     tenant_id: &str,
-) -> Result<Vec<ApplicationResponse>, Box<dyn Error>> {
+) -> Paginator<'a, ApplicationResponse> {
     // paginate-applications-for-tenant-by-id
 
-    let authorized_user = ensure_auth().await?;
-    let query = vec![("$top", "10000")];
-
     let endpoint = format!("/tenants/{}/applications", tenant_id);
-    let response: ApplicationCollectionResponse =
-        perform_get_request(&authorized_user.credential, &endpoint, query)
-            .await?;
-    let applications = response.data;
 
-    Ok(applications)
+    Paginator::new(session, endpoint)
 }
 
 pub async fn get_application(
+    session: &ApiSession,
     tenant_id: &str,
     application_id: &str,
 ) -> Result<ApplicationResponse, Box<dyn Error>> {
     // get-application-by-id-for-tenant-by-id
 
-    let authorized_user = ensure_auth().await?;
-
     let endpoint =
         format!("/tenants/{}/applications/{}", tenant_id, application_id);
-    let response: ApplicationResponse =
-        perform_get_request(&authorized_user.credential, &endpoint, vec![])
-            .await?;
+    let response: ApplicationResponse = session.get(&endpoint, vec![]).await?;
 
     Ok(response)
 }
 
 pub async fn create_application(
+    session: &ApiSession,
     tenant_id: &str,
     name: &str,
     bundle_id: &str,
+    description: Option<&str>,
+    is_new_project: bool,
 ) -> Result<ApplicationResponse, Box<dyn Error>> {
     // create-application-for-tenant-by-id
 
-    let authorized_user = ensure_auth().await?;
-
     let endpoint = format!("/tenants/{}/applications", tenant_id);
     let body = ApplicationRequest {
         name: name.to_string(),
-        description: None,
+        description: description.map(|description| description.to_string()),
         r#type: ApplicationType::Ios,
         ios_bundle_id: bundle_id.to_string(),
-        is_new_project: true,
+        is_new_project,
     };
 
-    let response: ApplicationResponse = perform_request_with_body(
-        &authorized_user.credential,
-        &endpoint,
-        reqwest::Method::POST,
-        body,
-    )
-    .await?;
+    let response: ApplicationResponse = session
+        .send_with_body(&endpoint, reqwest::Method::POST, body)
+        .await?;
 
     Ok(response)
 }
 
-async fn ensure_auth() -> Result<AuthorizedUser, Box<dyn Error>> {
-    let credential = auth::perform_device_authentication().await?;
+pub async fn update_application(
+    session: &ApiSession,
+    tenant_id: &str,
+    application_id: &str,
+    name: Option<&str>,
+    description: Option<&str>,
+    bundle_id: Option<&str>,
+) -> Result<ApplicationResponse, Box<dyn Error>> {
+    // update-application-by-id-for-tenant-by-id
+
+    let endpoint =
+        format!("/tenants/{}/applications/{}", tenant_id, application_id);
+    let body = UpdateApplicationRequest {
+        name: name.map(|name| name.to_string()),
+        description: description.map(|description| description.to_string()),
+        ios_bundle_id: bundle_id.map(|bundle_id| bundle_id.to_string()),
+    };
 
-    let response: UserInfoResponse =
-        perform_get_request(&credential, "/user-info", vec![]).await?;
+    let response: ApplicationResponse = session
+        .send_with_body(&endpoint, reqwest::Method::PUT, body)
+        .await?;
 
-    Ok(AuthorizedUser {
-        credential,
-        user: response.user,
-    })
+    Ok(response)
 }
 
-async fn perform_get_request<T: DeserializeOwned>(
+pub async fn delete_application(
+    session: &ApiSession,
+    tenant_id: &str,
+    application_id: &str,
+) -> Result<(), Box<dyn Error>> {
+    // delete-application-by-id-for-tenant-by-id
+
+    let endpoint =
+        format!("/tenants/{}/applications/{}", tenant_id, application_id);
+
+    session
+        .send_with_body(&endpoint, reqwest::Method::DELETE, ())
+        .await
+}
+
+pub async fn get_user_info(
     credential: &Credental,
+) -> Result<UserResponse, Box<dyn Error>> {
+    let response: UserInfoResponse = perform_get_request(
+        http_client::shared_client(),
+        credential.clone(),
+        "/user-info",
+        &[],
+    )
+    .await?;
+
+    Ok(response.user)
+}
+
+async fn perform_get_request<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    credential: Credental,
     endpoint: &str,
-    query: Vec<(&str, &str)>,
+    query: &[(&str, &str)],
 ) -> Result<T, Box<dyn Error>> {
-    let url = format!("https://api.parra.io/v1{}", endpoint);
-    let client = reqwest::Client::new();
+    let url = format!("{}{}", config::active_profile().api_url, endpoint);
     let token = &credential.token;
 
     let request = client
         .request(reqwest::Method::GET, url)
-        .query(&query)
+        .query(query)
         .bearer_auth(token);
 
-    let response = request.send().await?;
-    let body = response.text().await?;
+    let response = http_client::send(request)
+        .await
+        .map_err(|error| ApiError::Network(error.to_string()))?;
 
-    Ok(serde_json::from_str::<T>(&body)?)
+    parse_response(response).await
 }
 
 async fn perform_request_with_body<T: DeserializeOwned, U: Serialize>(
-    credential: &Credental,
+    client: &reqwest::Client,
+    credential: Credental,
     endpoint: &str,
     method: reqwest::Method,
-    body: U,
+    body: &U,
 ) -> Result<T, Box<dyn Error>> {
-    let url = format!("https://api.parra.io/v1{}", endpoint);
-    let client = reqwest::Client::new();
+    let url = format!("{}{}", config::active_profile().api_url, endpoint);
     let token = &credential.token;
 
     let mut request = client.request(method.clone(), url).bearer_auth(token);
 
-    if method != reqwest::Method::GET {
-        request = request.json(&body);
+    if method != reqwest::Method::GET && method != reqwest::Method::DELETE {
+        request = request.json(body);
+    }
+
+    let response = http_client::send(request)
+        .await
+        .map_err(|error| ApiError::Network(error.to_string()))?;
+
+    parse_response(response).await
+}
+
+/// Deserializes a successful response, or converts an unsuccessful one into
+/// an `ApiError` using the error details in the body.
+async fn parse_response<T: DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, Box<dyn Error>> {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    let body = response
+        .text()
+        .await
+        .map_err(|error| ApiError::Network(error.to_string()))?;
+
+    logging::log_response_body(&body);
+
+    if !status.is_success() {
+        return Err(Box::new(ApiError::from_response(
+            status.as_u16(),
+            &body,
+            retry_after,
+        )));
+    }
+
+    // Responses without content, such as to a delete, are parsed as null so
+    // that they can be deserialized as `()`.
+    let body = if body.trim().is_empty() {
+        "null"
+    } else {
+        &body
+    };
+
+    Ok(serde_json::from_str::<T>(body)
+        .map_err(|error| ApiError::InvalidResponse(error.to_string()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PageState, MAX_PAGES};
+    use crate::mock_server::{self, MockResponse};
+    use crate::types::api::{CollectionPage, CollectionResponse};
+    use reqwest::Url;
+    use serde_json::{json, Value};
+    use std::error::Error;
+
+    /// How the endpoint tells the CLI where the collection ends.
+    enum Style {
+        TotalCount,
+        NextLink,
+        PageSizeOnly,
+        IgnoresSkip,
     }
 
-    let response = request.send().await?;
+    fn items(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|index| json!({ "id": format!("item-{}", index) }))
+            .collect()
+    }
 
-    if !response.status().is_success() {
-        let body = response.text().await?;
-        return Err(format!("Error response received: {}", body).into());
+    /// Requests a page from the mock server's paginated endpoint, and reshapes
+    /// the response into the given style.
+    fn fetch(
+        items: &[Value],
+        query: Vec<(&str, String)>,
+        style: &Style,
+    ) -> CollectionResponse<Value> {
+        let query = query.into_iter().filter_map(|(key, value)| match key {
+            // Cursors are the offset of the next page.
+            "$cursor" => Some(("$skip", value)),
+            "$skip" if matches!(style, Style::IgnoresSkip) => None,
+            _ => Some((key, value)),
+        });
+        let url = Url::parse_with_params(
+            "http://localhost/v1/users/user-1/tenants",
+            query,
+        )
+        .unwrap();
+
+        let mut body = match mock_server::paginate(items.to_vec(), &url) {
+            MockResponse::Json(200, body) => body,
+            _ => panic!("The mock server didn't return a page."),
+        };
+
+        let total_count = body["total_count"].as_u64().unwrap();
+        let end = url
+            .query_pairs()
+            .find(|(key, _)| key == "$skip")
+            .map_or(0, |(_, skip)| skip.parse::<u64>().unwrap())
+            + body["data"].as_array().unwrap().len() as u64;
+        let page = body.as_object_mut().unwrap();
+
+        match style {
+            Style::TotalCount => {}
+            Style::NextLink => {
+                page.remove("total_count");
+                page.remove("page_size");
+
+                if end < total_count {
+                    page.insert("next_cursor".into(), json!(end.to_string()));
+                }
+            }
+            Style::PageSizeOnly | Style::IgnoresSkip => {
+                page.remove("total_count");
+            }
+        }
+
+        serde_json::from_value::<CollectionPage<Value>>(body)
+            .unwrap()
+            .into_response()
     }
 
-    let body = response.text().await?;
+    /// Lists every item, returning them and the number of requests made.
+    fn list(
+        items: &[Value],
+        style: Style,
+    ) -> Result<(Vec<Value>, u64), Box<dyn Error>> {
+        let mut pages = PageState::new(100);
+        let mut listed = vec![];
+        let mut requests = 0;
+
+        while !pages.finished {
+            let page = fetch(items, pages.query(), &style);
+            requests += 1;
+
+            if let Some(data) = pages.advance(page)? {
+                listed.extend(data);
+            }
+        }
+
+        Ok((listed, requests))
+    }
+
+    #[test]
+    fn stops_at_the_total_count() {
+        let items = items(250);
+
+        assert_eq!(list(&items, Style::TotalCount).unwrap(), (items, 3));
+    }
 
-    Ok(serde_json::from_str::<T>(&body)?)
+    #[test]
+    fn follows_next_links() {
+        let items = items(250);
+
+        assert_eq!(list(&items, Style::NextLink).unwrap(), (items, 3));
+    }
+
+    #[test]
+    fn stops_after_a_short_last_page() {
+        let short = items(250);
+
+        assert_eq!(list(&short, Style::PageSizeOnly).unwrap(), (short, 3));
+
+        // When the last page is full, an empty page marks the end.
+        let full = items(200);
+
+        assert_eq!(list(&full, Style::PageSizeOnly).unwrap(), (full, 3));
+    }
+
+    #[test]
+    fn stops_when_a_page_repeats() {
+        let items = items(250);
+        let (listed, requests) = list(&items, Style::IgnoresSkip).unwrap();
+
+        assert_eq!(listed, items[..100]);
+        assert_eq!(requests, 2);
+    }
+
+    #[test]
+    fn gives_up_after_the_maximum_number_of_pages() {
+        let items = vec![json!({ "name": "Item without an ID" }); 250];
+        let error = list(&items, Style::IgnoresSkip).unwrap_err();
+
+        assert!(error.to_string().contains(&MAX_PAGES.to_string()));
+    }
 }
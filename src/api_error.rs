@@ -0,0 +1,211 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+/// The body of an error response from the Parra API. Every field is optional
+/// since not every error includes all of them.
+#[derive(Debug, Deserialize, Default)]
+struct ErrorResponse {
+    message: Option<String>,
+    error: Option<String>,
+    #[serde(default)]
+    errors: Vec<FieldErrorResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldErrorResponse {
+    #[serde(alias = "path")]
+    field: Option<String>,
+    message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// A failed request to the Parra API.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The access token is missing, invalid or expired.
+    Unauthorized {
+        message: Option<String>,
+    },
+    /// The user doesn't have access to the resource.
+    Forbidden {
+        message: Option<String>,
+    },
+    NotFound {
+        message: Option<String>,
+    },
+    /// The request was rejected because of invalid input.
+    Validation {
+        message: Option<String>,
+        field_errors: Vec<FieldError>,
+    },
+    RateLimited {
+        /// Seconds to wait before trying again, from the `Retry-After` header.
+        retry_after: Option<u64>,
+    },
+    Server {
+        status: u16,
+        message: Option<String>,
+    },
+    /// Any other unsuccessful status.
+    Unexpected {
+        status: u16,
+        message: Option<String>,
+    },
+    /// The request didn't complete, for example because the server couldn't
+    /// be reached.
+    Network(String),
+    /// The request succeeded, but the response couldn't be parsed.
+    InvalidResponse(String),
+    /// The server no longer supports this version of the CLI for requests
+    /// that make changes.
+    UnsupportedVersion {
+        minimum: String,
+    },
+}
+
+impl ApiError {
+    pub fn from_response(
+        status: u16,
+        body: &str,
+        retry_after: Option<u64>,
+    ) -> Self {
+        let response =
+            serde_json::from_str::<ErrorResponse>(body).unwrap_or_default();
+        let message = response.message.or(response.error);
+
+        match status {
+            401 => ApiError::Unauthorized { message },
+            403 => ApiError::Forbidden { message },
+            404 => ApiError::NotFound { message },
+            400 | 422 => ApiError::Validation {
+                message,
+                field_errors: response
+                    .errors
+                    .into_iter()
+                    .map(|error| FieldError {
+                        field: error.field,
+                        message: error.message,
+                    })
+                    .collect(),
+            },
+            429 => ApiError::RateLimited { retry_after },
+            500..=599 => ApiError::Server { status, message },
+            _ => ApiError::Unexpected { status, message },
+        }
+    }
+
+    /// The exit code the CLI uses when a command fails with this error. These
+    /// are stable so that scripts can depend on them.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApiError::Unauthorized { .. } => 3,
+            ApiError::Forbidden { .. } => 4,
+            ApiError::NotFound { .. } => 5,
+            ApiError::Validation { .. } => 6,
+            ApiError::RateLimited { .. } => 7,
+            ApiError::Server { .. } => 8,
+            ApiError::Network(_) => 9,
+            ApiError::Unexpected { .. } | ApiError::InvalidResponse(_) => 10,
+            ApiError::UnsupportedVersion { .. } => 11,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unauthorized { message } => write!(
+                f,
+                "Your session is no longer valid ({}). Run `parra login` to log in again.",
+                message.as_deref().unwrap_or("unauthorized")
+            ),
+            ApiError::Forbidden { message } => write!(
+                f,
+                "You don't have access to this resource. {}",
+                message.as_deref().unwrap_or("Check that you're logged in to the right account with `parra whoami`.")
+            ),
+            ApiError::NotFound { message } => write!(
+                f,
+                "Not found. {}",
+                message
+                    .as_deref()
+                    .unwrap_or("Check that the provided IDs are correct.")
+            ),
+            ApiError::Validation {
+                message,
+                field_errors,
+            } => {
+                write!(f, "The request was invalid.")?;
+
+                if let Some(message) = message {
+                    write!(f, " {}", message)?;
+                }
+
+                for field_error in field_errors {
+                    match &field_error.field {
+                        Some(field) => {
+                            write!(f, "\n  {}: {}", field, field_error.message)?
+                        }
+                        None => write!(f, "\n  {}", field_error.message)?,
+                    }
+                }
+
+                Ok(())
+            }
+            ApiError::RateLimited { retry_after } => match retry_after {
+                Some(seconds) => write!(
+                    f,
+                    "Too many requests. Try again in {} seconds.",
+                    seconds
+                ),
+                None => write!(f, "Too many requests. Try again later."),
+            },
+            ApiError::Server { status, message } => write!(
+                f,
+                "The Parra API encountered an error ({}). {}",
+                status,
+                message.as_deref().unwrap_or("Try again later.")
+            ),
+            ApiError::Unexpected { status, message } => match message {
+                Some(message) => write!(
+                    f,
+                    "Request failed with status {}. {}",
+                    status, message
+                ),
+                None => write!(f, "Request failed with status {}.", status),
+            },
+            ApiError::Network(message) => write!(
+                f,
+                "Unable to reach the Parra API. Check your connection. ({})",
+                message
+            ),
+            ApiError::InvalidResponse(message) => write!(
+                f,
+                "Received an unexpected response from the Parra API: {}",
+                message
+            ),
+            ApiError::UnsupportedVersion { minimum } => write!(
+                f,
+                "This version of the Parra CLI ({}) is no longer supported. Version {} or later is required to make changes. Upgrade with `brew upgrade parra-inc/parra/parra-cli`.",
+                env!("CARGO_PKG_VERSION"),
+                minimum
+            ),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+/// The exit code to use for an error returned from a command.
+pub fn exit_code_for_error(error: &(dyn Error + 'static)) -> i32 {
+    match error.downcast_ref::<ApiError>() {
+        Some(api_error) => api_error.exit_code(),
+        None => 1,
+    }
+}
@@ -1,10 +1,35 @@
-use clap::{Parser, Subcommand};
+use crate::output::OutputFormat;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(version, about, long_about = "")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// The named environment profile to use, such as production, staging or
+    /// local. Can also be set with the PARRA_PROFILE environment variable.
+    /// Additional profiles can be defined in the Parra config.json file.
+    #[arg(long = "profile", global = true)]
+    pub profile: Option<String>,
+
+    /// The signed in account to use for this command, identified by its ID,
+    /// email or name. Defaults to the active account, which can be changed
+    /// with `parra auth switch`.
+    #[arg(long = "account", global = true)]
+    pub account: Option<String>,
+
+    /// Logs the HTTP requests made by the command to stderr. Pass once for
+    /// the method, URL, status and timing of each request, twice to include
+    /// headers and three times to include bodies. Secrets are redacted. Can
+    /// also be set with the PARRA_LOG environment variable.
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// The format to print results in. Messages about progress are printed
+    /// to stderr, so that results can be piped to other tools.
+    #[arg(short = 'o', long = "output", value_enum, default_value_t = OutputFormat::Table, global = true)]
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -32,6 +57,265 @@ pub enum Commands {
         /// value, you will be prompted to enter a path.
         #[arg(short = 'p', long = "project-path")]
         project_path: Option<String>,
+
+        /// The name of the application to use. An existing application in the
+        /// workspace with this name is used if there is one, otherwise a new
+        /// application is created with it.
+        #[arg(long = "app-name", conflicts_with = "application_id")]
+        app_name: Option<String>,
+
+        /// The bundle ID of the application, if a new one is created. Defaults
+        /// to one derived from the workspace and application names. Existing
+        /// applications keep their bundle ID.
+        #[arg(long = "bundle-id", conflicts_with = "application_id")]
+        bundle_id: Option<String>,
+
+        /// The name of the workspace to use. An existing workspace with this
+        /// name is used if there is one, otherwise a new workspace is created
+        /// with it.
+        #[arg(long = "workspace-name", conflicts_with = "workspace_id")]
+        workspace_name: Option<String>,
+
+        /// A JSON or YAML file with answers to the questions that would
+        /// otherwise be prompted for. Options take precedence over the file,
+        /// and questions it doesn't answer are still prompted for.
+        #[arg(long = "answers")]
+        answers: Option<String>,
+
+        /// Replace the project directory if it already exists.
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Install any missing dependencies without asking first.
+        #[arg(long = "install-deps")]
+        install_deps: bool,
+
+        /// Answer yes to every confirmation, including replacing an existing
+        /// project directory and installing missing dependencies.
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Never prompt for input. If anything needed to bootstrap the project
+        /// wasn't provided, the command fails with a list of the missing
+        /// options instead.
+        #[arg(long = "non-interactive")]
+        non_interactive: bool,
+
+        /// Print what bootstrapping would do without doing it: the API calls,
+        /// the directories and files of the project, and the commands that
+        /// would be run. Workspaces and applications are looked up, but
+        /// nothing is created.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+
+        /// With --dry-run, also write the rendered project files to this
+        /// directory, so that they can be compared with an existing project.
+        #[arg(long = "scratch-dir", requires = "dry_run")]
+        scratch_dir: Option<String>,
+    },
+
+    /// Signs in to your Parra account. This opens the browser to confirm your
+    /// identity and stores the resulting credential for future commands.
+    Login {
+        /// How to confirm your identity. `device` shows a code to confirm in
+        /// the browser, and `browser` completes the login with a single click
+        /// by redirecting back to a temporary listener on localhost.
+        #[arg(long = "method", value_enum, default_value_t = LoginMethod::Device)]
+        method: LoginMethod,
+
+        /// Don't open the browser. Instead, display a QR code and a code to
+        /// confirm the login from another device. Useful over SSH.
+        #[arg(long = "no-browser")]
+        no_browser: bool,
+    },
+
+    /// Signs out of your Parra account. The stored credential is revoked and
+    /// removed from this machine.
+    Logout,
+
+    /// Displays information about the currently signed in user, including
+    /// when the current access token expires.
+    Whoami,
+
+    /// Manages the accounts that are signed in to the CLI.
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+
+    /// Manages your Parra workspaces.
+    Workspaces {
+        #[command(subcommand)]
+        command: WorkspacesCommands,
+    },
+
+    /// Manages the applications in your Parra workspaces.
+    Apps {
+        #[command(subcommand)]
+        command: AppsCommands,
+    },
+
+    /// Tools for developing and testing the CLI without the Parra service.
+    Dev {
+        #[command(subcommand)]
+        command: DevCommands,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LoginMethod {
+    Device,
+    Browser,
+}
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Lists every account that is signed in for the current profile. The
+    /// active account is marked with an asterisk.
+    List,
+
+    /// Changes the active account. Commands will use this account unless the
+    /// `--account` flag is provided.
+    Switch {
+        /// The ID, email or name of the account to switch to.
+        user: String,
+    },
+
+    /// Prints the access token for the current account, refreshing it first
+    /// if it is about to expire. Use this to call the Parra API from your own
+    /// scripts with the same session as the CLI.
+    Token,
+
+    /// Displays the current account, the scopes granted to its access token
+    /// and how long until the token expires.
+    Status {
+        /// Print the status as JSON, including the claims decoded from the
+        /// access token.
+        #[arg(long = "json")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkspacesCommands {
+    /// Lists every workspace you have access to.
+    List,
+
+    /// Displays the details of a workspace, including its domains and logo.
+    Show {
+        /// The ID of the workspace.
+        workspace_id: String,
+    },
+
+    /// Creates a new workspace.
+    Create {
+        /// The name of the workspace.
+        name: String,
+
+        /// Create a test workspace, which can be used to try out Parra.
+        #[arg(long = "test")]
+        test: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AppsCommands {
+    /// Lists the applications in a workspace, or in every workspace you have
+    /// access to.
+    List {
+        /// The ID of the workspace to list the applications of.
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<String>,
+    },
+
+    /// Displays the details of an application.
+    Show {
+        /// The ID of the application.
+        application_id: String,
+
+        /// The ID of the workspace that owns the application. If you don't
+        /// provide this value, every workspace you have access to is searched.
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<String>,
+    },
+
+    /// Creates a new iOS application without generating a project.
+    Create {
+        /// The ID of the workspace to create the application in. Can be
+        /// omitted if you only have one workspace.
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<String>,
+
+        /// The name of the application.
+        #[arg(long = "name")]
+        name: String,
+
+        /// The bundle ID of the application, in reverse-DNS format. For
+        /// example: com.example.my-app
+        #[arg(long = "bundle-id")]
+        bundle_id: String,
+
+        /// A description of the application.
+        #[arg(long = "description")]
+        description: Option<String>,
+    },
+
+    /// Changes the name, description or bundle ID of an application. Only
+    /// the values that are provided are changed.
+    Update {
+        /// The ID of the application to update.
+        application_id: String,
+
+        /// The ID of the workspace that owns the application. If you don't
+        /// provide this value, every workspace you have access to is searched.
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<String>,
+
+        /// The new name of the application.
+        #[arg(long = "name")]
+        name: Option<String>,
+
+        /// The new description of the application.
+        #[arg(long = "description")]
+        description: Option<String>,
+
+        /// The new bundle ID of the application, in reverse-DNS format.
+        #[arg(long = "bundle-id")]
+        bundle_id: Option<String>,
+    },
+
+    /// Permanently deletes an application. This can't be undone.
+    Delete {
+        /// The ID of the application to delete.
+        application_id: String,
+
+        /// The ID of the workspace that owns the application. If you don't
+        /// provide this value, every workspace you have access to is searched.
+        #[arg(short = 'w', long = "workspace")]
+        workspace: Option<String>,
+
+        /// The name of the application, to confirm that it is the one you
+        /// want to delete.
+        #[arg(long = "confirm", value_name = "NAME")]
+        confirm: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DevCommands {
+    /// Runs a mock of the Parra API and authorization server on localhost.
+    /// Point the CLI at it with the PARRA_API_URL and PARRA_AUTH_URL
+    /// environment variables, or use the local profile with the default port.
+    MockServer {
+        /// The port to listen on.
+        #[arg(long = "port", default_value_t = crate::config::LOCAL_PORT)]
+        port: u16,
+
+        /// A JSON file with the user, workspaces and applications to serve,
+        /// and how the device login flow should behave. A small set of sample
+        /// data is served if this isn't provided.
+        #[arg(long = "fixtures")]
+        fixtures: Option<String>,
     },
 }
 
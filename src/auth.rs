@@ -1,104 +1,378 @@
+use crate::accounts::{self, Account};
+use crate::types::api::AuthorizedUser;
 use crate::types::auth::{
-    AuthResponse, Credental, DeviceAuthResponse, RefreshResponse, TokenRequest,
+    AuthResponse, ClientCredentialsResponse, Credental, DeviceAuthResponse,
+    OAuthError, OAuthErrorResponse, RefreshResponse, TokenRequest,
 };
+use crate::{api, config, credential_store, http_client, jwt, logging, pkce};
 use inquire::Confirm;
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use reqwest::Url;
 use serde::de::DeserializeOwned;
+use std::env;
 use std::error::Error;
+use std::fmt;
+use std::io::{stderr, stdin, IsTerminal, Write};
 use std::ops::Add;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const AUTH0_CLIENT_ID: &str = "nD9GTUvvqCT0oWi34L2IdJiK0YjupSjY";
+const ACCESS_TOKEN_ENV_VAR: &str = "PARRA_ACCESS_TOKEN";
+const REFRESH_TOKEN_ENV_VAR: &str = "PARRA_REFRESH_TOKEN";
+const CLIENT_ID_ENV_VAR: &str = "PARRA_CLIENT_ID";
+const CLIENT_SECRET_ENV_VAR: &str = "PARRA_CLIENT_SECRET";
+const CLIENT_AUDIENCE_ENV_VAR: &str = "PARRA_CLIENT_AUDIENCE";
 
 pub async fn perform_device_authentication() -> Result<Credental, Box<dyn Error>>
 {
-    match get_persisted_credential() {
-        Ok(credential) => {
-            let now = SystemTime::now();
-            let timestamp = now.duration_since(UNIX_EPOCH)?.as_secs();
-
-            // Token is either already expired or about to expire
-            if timestamp > credential.expiry - 30 {
-                return perform_refresh_authentication(&credential).await;
-            } else {
+    // Credentials provided through the environment always take precedence over
+    // anything that is stored, so that CI never touches the credential store.
+    if let Some(credential) = get_environment_credential().await? {
+        return Ok(credential);
+    }
+
+    let account = match accounts::current_account()? {
+        Some(account) => account,
+        None => {
+            if let Some(credential) = migrate_legacy_credential().await? {
                 return Ok(credential);
             }
+
+            return Ok(perform_normal_authentication(true).await?.credential);
         }
+    };
+
+    let key = accounts::credential_key(&account.id);
+
+    let credential = match get_persisted_credential(&key) {
+        Ok(credential) => credential,
         Err(_) => {
-            return perform_normal_authentication().await;
+            return Ok(perform_normal_authentication(true).await?.credential)
+        }
+    };
+
+    // Token is either already expired or about to expire
+    if !is_expiring(&credential)? {
+        return Ok(credential);
+    }
+
+    match perform_refresh_authentication(&account, &credential).await {
+        Err(error) if is_rejected_refresh_token(error.as_ref()) => {
+            // The session was revoked or has expired, so the only way to
+            // continue is to log in again.
+            eprintln!("Your session has expired. Please log in again.");
+
+            forget_account(&account.id)?;
+
+            Ok(perform_normal_authentication(true).await?.credential)
         }
+        result => result,
+    }
+}
+
+/// Whether there is a credential available without starting the login flow,
+/// either from the environment or from the credential store.
+pub fn has_credential() -> bool {
+    if has_environment_credential() {
+        return true;
+    }
+
+    if let Ok(Some(account)) = accounts::current_account() {
+        let key = accounts::credential_key(&account.id);
+
+        return get_persisted_credential(&key).is_ok();
     }
+
+    get_persisted_credential(&config::active_profile().credential_key()).is_ok()
+}
+
+/// The credential that commands would use, without refreshing it or starting
+/// the login flow. Returns `None` if there isn't one.
+pub async fn get_current_credential(
+) -> Result<Option<Credental>, Box<dyn Error>> {
+    if let Some(credential) = get_environment_credential().await? {
+        return Ok(Some(credential));
+    }
+
+    let key = match accounts::current_account()? {
+        Some(account) => accounts::credential_key(&account.id),
+        None => config::active_profile().credential_key(),
+    };
+
+    Ok(get_persisted_credential(&key).ok())
 }
 
 async fn perform_refresh_authentication(
+    account: &Account,
     credential: &Credental,
 ) -> Result<Credental, Box<dyn Error>> {
-    let result: Result<RefreshResponse, Box<dyn Error>> = post_form_request(
-        "https://auth.parra.io/oauth/token",
+    let refresh_response =
+        request_refreshed_token(&credential.refresh_token).await?;
+
+    // This goes to stderr so that it doesn't end up in the output of commands
+    // like `parra auth token`.
+    eprintln!("Reauthentication successful!");
+
+    persist_refresh_credential(&account.id, &refresh_response, credential)
+}
+
+/// Gets a new access token after the API rejected `credential` as
+/// unauthorized, which can happen before it expires if it was revoked. Returns
+/// `None` if a new one can't be obtained without logging in again.
+pub async fn refresh_rejected_credential(
+    user_id: &str,
+    credential: &Credental,
+) -> Result<Option<Credental>, Box<dyn Error>> {
+    if has_environment_credential() {
+        // An access token provided directly can't be refreshed unless a
+        // refresh token was provided with it.
+        if credential.refresh_token.is_empty() {
+            if env::var(ACCESS_TOKEN_ENV_VAR).is_ok() {
+                return Ok(None);
+            }
+
+            return get_environment_credential().await;
+        }
+
+        let response =
+            request_refreshed_token(&credential.refresh_token).await?;
+
+        return Ok(Some(Credental {
+            expiry: token_expiry(&response.access_token, response.expires_in)?,
+            token: response.access_token,
+            refresh_token: response
+                .refresh_token
+                .unwrap_or(credential.refresh_token.clone()),
+            scope: Some(response.scope),
+        }));
+    }
+
+    let refresh_response =
+        match request_refreshed_token(&credential.refresh_token).await {
+            Ok(refresh_response) => refresh_response,
+            // The session has been revoked, so the original error, which
+            // asks the user to log in again, is the most useful one.
+            Err(error) if is_rejected_refresh_token(error.as_ref()) => {
+                forget_account(user_id)?;
+
+                return Ok(None);
+            }
+            Err(error) => return Err(error),
+        };
+
+    Ok(Some(persist_refresh_credential(
+        user_id,
+        &refresh_response,
+        credential,
+    )?))
+}
+
+/// Deletes the stored credential of an account whose session was revoked or
+/// has expired, and removes the account, since it can't be used until the
+/// user logs in to it again.
+fn forget_account(account_id: &str) -> Result<(), Box<dyn Error>> {
+    credential_store::default_store()?
+        .delete(&accounts::credential_key(account_id))?;
+
+    accounts::remove_account(account_id)
+}
+
+/// Before multiple accounts were supported, the credential for a profile was
+/// stored without a user ID. If one exists, move it under the user it belongs
+/// to so that upgrading doesn't require logging in again.
+async fn migrate_legacy_credential() -> Result<Option<Credental>, Box<dyn Error>>
+{
+    let legacy_key = config::active_profile().credential_key();
+
+    let mut credential = match get_persisted_credential(&legacy_key) {
+        Ok(credential) => credential,
+        Err(_) => return Ok(None),
+    };
+
+    if is_expiring(&credential)? {
+        let refresh_response =
+            request_refreshed_token(&credential.refresh_token).await?;
+
+        credential = Credental {
+            expiry: token_expiry(
+                &refresh_response.access_token,
+                refresh_response.expires_in,
+            )?,
+            token: refresh_response.access_token,
+            refresh_token: refresh_response
+                .refresh_token
+                .unwrap_or(credential.refresh_token),
+            scope: Some(refresh_response.scope),
+        };
+    }
+
+    let user = api::get_user_info(&credential).await?;
+
+    store_credential(&user.id, &credential)?;
+    accounts::add_account(Account::from(user))?;
+    credential_store::default_store()?.delete(&legacy_key)?;
+
+    Ok(Some(credential))
+}
+
+fn is_expiring(credential: &Credental) -> Result<bool, Box<dyn Error>> {
+    let now = SystemTime::now();
+    let timestamp = now.duration_since(UNIX_EPOCH)?.as_secs();
+
+    Ok(timestamp > credential.expiry.saturating_sub(30))
+}
+
+async fn request_refreshed_token(
+    refresh_token: &str,
+) -> Result<RefreshResponse, Box<dyn Error>> {
+    let profile = config::active_profile();
+
+    post_form_request(
+        &format!("{}/oauth/token", profile.auth_url),
         vec![
-            ("client_id".to_string(), AUTH0_CLIENT_ID.to_string()),
-            (
-                "refresh_token".to_string(),
-                credential.refresh_token.to_string(),
-            ),
+            ("client_id".to_string(), profile.client_id.clone()),
+            ("refresh_token".to_string(), refresh_token.to_string()),
             ("grant_type".to_string(), "refresh_token".to_string()),
         ],
     )
-    .await;
+    .await
+}
+
+pub fn has_environment_credential() -> bool {
+    env::var(ACCESS_TOKEN_ENV_VAR).is_ok()
+        || env::var(REFRESH_TOKEN_ENV_VAR).is_ok()
+        || env::var(CLIENT_SECRET_ENV_VAR).is_ok()
+}
+
+/// Builds a credential from the environment for non-interactive use. In order
+/// of precedence, this uses `PARRA_ACCESS_TOKEN` directly, exchanges
+/// `PARRA_REFRESH_TOKEN` for an access token, or performs a client credentials
+/// grant with `PARRA_CLIENT_ID` and `PARRA_CLIENT_SECRET`. Credentials from the
+/// environment are never persisted.
+async fn get_environment_credential(
+) -> Result<Option<Credental>, Box<dyn Error>> {
+    let refresh_token = env::var(REFRESH_TOKEN_ENV_VAR).ok();
+
+    if let Ok(access_token) = env::var(ACCESS_TOKEN_ENV_VAR) {
+        // If the token provided directly doesn't say when it expires, it is
+        // left to the server to reject it once it does.
+        let expiry = expiry_claim(&access_token).unwrap_or(u64::MAX);
+
+        return Ok(Some(Credental {
+            token: access_token,
+            refresh_token: refresh_token.unwrap_or_default(),
+            expiry,
+            scope: None,
+        }));
+    }
+
+    if let Some(refresh_token) = refresh_token {
+        let response = request_refreshed_token(&refresh_token).await?;
 
-    let refresh_response = result?;
+        return Ok(Some(Credental {
+            expiry: token_expiry(&response.access_token, response.expires_in)?,
+            token: response.access_token,
+            refresh_token: response.refresh_token.unwrap_or(refresh_token),
+            scope: Some(response.scope),
+        }));
+    }
 
-    println!("Reauthentication successful!");
+    if let Ok(client_secret) = env::var(CLIENT_SECRET_ENV_VAR) {
+        let client_id = env::var(CLIENT_ID_ENV_VAR).map_err(|_| {
+            format!(
+                "{} must be set when {} is provided.",
+                CLIENT_ID_ENV_VAR, CLIENT_SECRET_ENV_VAR
+            )
+        })?;
+
+        let mut fields = vec![
+            ("client_id".to_string(), client_id),
+            ("client_secret".to_string(), client_secret),
+            ("grant_type".to_string(), "client_credentials".to_string()),
+        ];
+
+        if let Ok(audience) = env::var(CLIENT_AUDIENCE_ENV_VAR) {
+            fields.push(("audience".to_string(), audience));
+        }
 
-    return persist_refresh_credential(&refresh_response, &credential);
+        let response: ClientCredentialsResponse = post_form_request(
+            &format!("{}/oauth/token", config::active_profile().auth_url),
+            fields,
+        )
+        .await?;
+
+        return Ok(Some(Credental {
+            expiry: token_expiry(&response.access_token, response.expires_in)?,
+            token: response.access_token,
+            refresh_token: String::new(),
+            scope: response.scope,
+        }));
+    }
+
+    Ok(None)
 }
 
-async fn perform_normal_authentication() -> Result<Credental, Box<dyn Error>> {
-    let device_code_url = "https://auth.parra.io/oauth/device/code";
+/// Logs in with the device authorization flow. When `open_browser` is false,
+/// the verification URL is displayed as a QR code instead of being opened, so
+/// that the login can be confirmed on another device.
+pub async fn perform_normal_authentication(
+    open_browser: bool,
+) -> Result<AuthorizedUser, Box<dyn Error>> {
+    ensure_interactive()?;
+
+    let profile = config::active_profile();
+    let device_code_url = format!("{}/oauth/device/code", profile.auth_url);
 
     let device_auth_response: Result<DeviceAuthResponse, Box<dyn Error>> =
         post_form_request(
-            device_code_url,
+            &device_code_url,
             vec![
-                ("client_id".to_string(), AUTH0_CLIENT_ID.to_string()),
+                ("client_id".to_string(), profile.client_id.clone()),
                 ("scope".to_string(), "offline_access".to_string()),
             ],
         )
         .await;
 
-    let device_auth = device_auth_response.unwrap();
-
-    // confirm that the user wants to open the browser
-    let confirm_message =
-        format!("We need to confirm your identity in the browser. Press enter to open the browser.");
-    let help_message = format!(
-        "If the browser doesn't open automatically, visit {} and enter the code: {} to confirm your login.",
-        device_auth.verification_uri, device_auth.user_code
-    );
+    let device_auth = device_auth_response.map_err(|error| {
+        format!("Failed to start the login process: {}", error)
+    })?;
+
+    if open_browser {
+        // confirm that the user wants to open the browser
+        let confirm_message =
+            "We need to confirm your identity in the browser. Press enter to open the browser.".to_string();
+        let help_message = format!(
+            "If the browser doesn't open automatically, visit {} and enter the code: {} to confirm your login.",
+            device_auth.verification_uri, device_auth.user_code
+        );
 
-    let confirmed = Confirm::new(&confirm_message)
-        .with_default(true)
-        .with_help_message(&help_message)
-        .prompt()?;
+        let confirmed = Confirm::new(&confirm_message)
+            .with_default(true)
+            .with_help_message(&help_message)
+            .prompt()?;
 
-    if !confirmed {
-        return Err("Authentication cancelled".into());
-    }
+        if !confirmed {
+            return Err("Authentication cancelled".into());
+        }
 
-    let result = open::that(device_auth.verification_uri_complete);
+        let result = open::that(&device_auth.verification_uri_complete);
 
-    if result.is_err() {
-        println!(
-            "Failed to open the browser. Please visit {} and enter the code: {} to confirm your login.",
-            device_auth.verification_uri,
-            device_auth.user_code
-        );
+        if result.is_err() {
+            eprintln!(
+                "Failed to open the browser. Please visit {} and enter the code: {} to confirm your login.",
+                device_auth.verification_uri,
+                device_auth.user_code
+            );
+        }
+    } else {
+        print_verification_qr_code(&device_auth)?;
     }
 
     // begin polling for the token
-    let token_url = "https://auth.parra.io/oauth/token";
+    let token_url = format!("{}/oauth/token", profile.auth_url);
 
     let token_request_body = TokenRequest {
-        client_id: AUTH0_CLIENT_ID.to_string(),
+        client_id: profile.client_id.clone(),
         device_code: device_auth.device_code,
         grant_type: "urn:ietf:params:oauth:grant-type:device_code".to_string(),
     };
@@ -108,91 +382,363 @@ async fn perform_normal_authentication() -> Result<Credental, Box<dyn Error>> {
     // aren't awaiting the result of that web page being opened, we can launch it, then
     // begin polling.
     let poll_result = poll_for_token::<AuthResponse>(
-        token_url,
+        &token_url,
         device_auth.interval,
         device_auth.expires_in,
         token_request_body,
+        !open_browser,
     )
-    .await?;
+    .await;
 
-    println!("Authentication successful!");
+    if !open_browser {
+        // Move past the countdown line before printing anything else.
+        eprintln!();
+    }
 
-    return persist_credential(
-        &poll_result.access_token,
-        poll_result.expires_in,
-        &poll_result.refresh_token,
-    );
+    let poll_result = poll_result?;
+
+    eprintln!("Authentication successful!");
+
+    complete_login(poll_result).await
 }
 
-fn get_persisted_credential() -> Result<Credental, Box<dyn Error>> {
-    let data = security_framework::passwords::get_generic_password(
-        "parra_cli",
-        AUTH0_CLIENT_ID,
+/// Logs in with the authorization code flow using PKCE. The browser is sent to
+/// the authorization server, which redirects back to a temporary listener on
+/// localhost with the authorization code once the user has logged in.
+pub async fn perform_authorization_code_authentication(
+) -> Result<AuthorizedUser, Box<dyn Error>> {
+    ensure_interactive()?;
+
+    let profile = config::active_profile();
+    let pkce_challenge = pkce::generate_pkce_challenge();
+    let state = pkce::generate_state();
+    let listener = pkce::LoopbackListener::bind()?;
+    let redirect_uri = listener.redirect_uri.clone();
+
+    let authorize_url = Url::parse_with_params(
+        &format!("{}/authorize", profile.auth_url),
+        &[
+            ("response_type", "code"),
+            ("client_id", profile.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", "openid profile email offline_access"),
+            ("code_challenge", pkce_challenge.challenge.as_str()),
+            ("code_challenge_method", "S256"),
+            ("state", state.as_str()),
+        ],
     )?;
 
+    eprintln!("Confirm your login in the browser. If it doesn't open automatically, visit:\n{}", authorize_url);
+
+    if open::that(authorize_url.as_str()).is_err() {
+        eprintln!(
+            "Failed to open the browser. Visit the URL above to continue."
+        );
+    }
+
+    let code = listener
+        .wait_for_code(state, Duration::from_secs(300))
+        .await?;
+
+    let token_response: AuthResponse = post_form_request(
+        &format!("{}/oauth/token", profile.auth_url),
+        vec![
+            ("grant_type".to_string(), "authorization_code".to_string()),
+            ("client_id".to_string(), profile.client_id.clone()),
+            ("code".to_string(), code),
+            ("code_verifier".to_string(), pkce_challenge.verifier.clone()),
+            ("redirect_uri".to_string(), redirect_uri),
+        ],
+    )
+    .await?;
+
+    eprintln!("Authentication successful!");
+
+    complete_login(token_response).await
+}
+
+/// Persists the credential from a successful login and makes the user it
+/// belongs to the active account. Returns the credential with its user.
+async fn complete_login(
+    response: AuthResponse,
+) -> Result<AuthorizedUser, Box<dyn Error>> {
+    let credential = Credental {
+        expiry: token_expiry(&response.access_token, response.expires_in)?,
+        token: response.access_token,
+        refresh_token: response.refresh_token,
+        scope: Some(response.scope),
+    };
+
+    // Credentials are stored per user, so the user needs to be known before
+    // the credential can be persisted.
+    let user = api::get_user_info(&credential).await?;
+
+    store_credential(&user.id, &credential)?;
+    accounts::add_account(Account::from(user.clone()))?;
+
+    Ok(AuthorizedUser { credential, user })
+}
+
+/// Logging in requires the user to confirm in the browser, which can't happen
+/// in CI. Fail instead of waiting for input that will never come.
+fn ensure_interactive() -> Result<(), Box<dyn Error>> {
+    if stdin().is_terminal() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Not logged in and unable to prompt for login because stdin is not a terminal. Set {} or {}, or {} and {} to authenticate non-interactively.",
+        ACCESS_TOKEN_ENV_VAR,
+        REFRESH_TOKEN_ENV_VAR,
+        CLIENT_ID_ENV_VAR,
+        CLIENT_SECRET_ENV_VAR
+    )
+    .into())
+}
+
+/// Revokes the refresh token of the current account's credential and removes
+/// it from the credential store. Returns the account that was logged out, or
+/// `None` if there wasn't one.
+pub async fn perform_logout() -> Result<Option<Account>, Box<dyn Error>> {
+    let account = match accounts::current_account()? {
+        Some(account) => account,
+        None => {
+            // A credential from before multiple accounts were supported
+            // doesn't have an account to report, but should still be removed.
+            let legacy_key = config::active_profile().credential_key();
+            revoke_and_delete_credential(&legacy_key).await?;
+
+            return Ok(None);
+        }
+    };
+
+    revoke_and_delete_credential(&accounts::credential_key(&account.id))
+        .await?;
+    accounts::remove_account(&account.id)?;
+
+    Ok(Some(account))
+}
+
+async fn revoke_and_delete_credential(key: &str) -> Result<(), Box<dyn Error>> {
+    let credential = match get_persisted_credential(key) {
+        Ok(credential) => credential,
+        Err(_) => return Ok(()),
+    };
+
+    // Failing to revoke the token shouldn't prevent the user from logging out
+    // locally, since the credential is deleted either way.
+    if let Err(error) = revoke_refresh_token(&credential.refresh_token).await {
+        eprintln!("Failed to revoke refresh token: {}", error);
+    }
+
+    credential_store::default_store()?.delete(key)
+}
+
+fn get_persisted_credential(key: &str) -> Result<Credental, Box<dyn Error>> {
+    let store = credential_store::default_store()?;
+    let data = store.read(key)?;
+
     let data = String::from_utf8(data)?;
 
     return Ok(serde_json::from_str::<Credental>(&data)?);
 }
 
 fn persist_refresh_credential(
+    account_id: &str,
     data: &RefreshResponse,
     existing_credential: &Credental,
 ) -> Result<Credental, Box<dyn Error>> {
     let next_credential = Credental {
         token: data.access_token.clone(),
-        refresh_token: existing_credential.refresh_token.clone(),
-        expiry: existing_credential.expiry,
+        // Refresh tokens may be rotated, in which case the old one will no
+        // longer be accepted.
+        refresh_token: data
+            .refresh_token
+            .clone()
+            .unwrap_or(existing_credential.refresh_token.clone()),
+        expiry: token_expiry(&data.access_token, data.expires_in)?,
+        scope: Some(data.scope.clone()),
     };
 
-    return persist_credential(
-        &next_credential.token,
-        data.expires_in,
-        &next_credential.refresh_token,
-    );
+    store_credential(account_id, &next_credential)?;
+
+    Ok(next_credential)
+}
+
+fn store_credential(
+    account_id: &str,
+    credential: &Credental,
+) -> Result<(), Box<dyn Error>> {
+    let serialized = serde_json::to_string(credential).unwrap();
+
+    let store = credential_store::default_store()?;
+    store.write(&accounts::credential_key(account_id), serialized.as_bytes())
 }
 
-fn persist_credential(
+/// When the access token expires. This prefers the `exp` claim of the token,
+/// since it was set by the server's clock and isn't affected by skew with the
+/// local clock. `expires_in` is only used for tokens without the claim.
+fn token_expiry(
     access_token: &str,
     expires_in: u64,
-    refresh_token: &str,
-) -> Result<Credental, Box<dyn Error>> {
+) -> Result<u64, Box<dyn Error>> {
+    match expiry_claim(access_token) {
+        Some(expiry) => Ok(expiry),
+        None => expiry_from_now(expires_in),
+    }
+}
+
+fn expiry_claim(access_token: &str) -> Option<u64> {
+    jwt::decode_claims(access_token).ok()?.get("exp")?.as_u64()
+}
+
+fn expiry_from_now(expires_in: u64) -> Result<u64, Box<dyn Error>> {
     let now = SystemTime::now();
-    let mut expiry = now.duration_since(UNIX_EPOCH)?;
-    expiry = expiry.add(Duration::from_secs(expires_in));
+    let expiry = now.duration_since(UNIX_EPOCH)?;
 
-    let credential = Credental {
-        token: access_token.to_string(),
-        expiry: expiry.as_secs(),
-        refresh_token: refresh_token.to_string(),
-    };
+    Ok(expiry.add(Duration::from_secs(expires_in)).as_secs())
+}
 
-    let serialized = serde_json::to_string(&credential).unwrap();
+/// A request to the authorization server that was rejected.
+#[derive(Debug)]
+pub struct AuthRequestError {
+    pub status: u16,
+    pub error: Option<OAuthErrorResponse>,
+    pub body: String,
+}
 
-    security_framework::passwords::set_generic_password(
-        "parra_cli",
-        AUTH0_CLIENT_ID,
-        serialized.as_bytes(),
-    )?;
+impl fmt::Display for AuthRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details = self
+            .error
+            .as_ref()
+            .and_then(|error| error.error_description.clone())
+            .unwrap_or(self.body.clone());
 
-    Ok(credential)
+        write!(f, "Request failed with status {}: {}", self.status, details)
+    }
+}
+
+impl Error for AuthRequestError {}
+
+/// Whether the error is the authorization server refusing a refresh token,
+/// which happens when it has expired, been revoked or already been rotated.
+fn is_rejected_refresh_token(error: &(dyn Error + 'static)) -> bool {
+    match error.downcast_ref::<AuthRequestError>() {
+        Some(AuthRequestError {
+            error: Some(error), ..
+        }) => error.error == OAuthError::Other("invalid_grant".to_string()),
+        Some(AuthRequestError { status, .. }) => {
+            *status == 401 || *status == 403
+        }
+        None => false,
+    }
 }
 
 async fn post_form_request<T: DeserializeOwned>(
     url: &str,
     fields: Vec<(String, String)>,
 ) -> Result<T, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let response = client.post(url).form(&fields).send().await?;
+    let client = http_client::shared_client();
+    let response = http_client::send(client.post(url).form(&fields)).await?;
 
     let status = response.status();
     let body = response.text().await?;
 
+    logging::log_response_body(&body);
+
     if status.is_success() {
-        return Ok(serde_json::from_str::<T>(&body)?);
+        Ok(serde_json::from_str::<T>(&body)?)
     } else {
-        eprintln!("Request failed with status {}: {}", status, body);
-        return Err("Request failed".into());
+        Err(Box::new(AuthRequestError {
+            status: status.as_u16(),
+            error: serde_json::from_str::<OAuthErrorResponse>(&body).ok(),
+            body,
+        }))
+    }
+}
+
+async fn revoke_refresh_token(
+    refresh_token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let profile = config::active_profile();
+    let client = http_client::shared_client();
+    let response = http_client::send(
+        client
+            .post(format!("{}/oauth/revoke", profile.auth_url))
+            .form(&[
+                ("client_id", profile.client_id.as_str()),
+                ("token", refresh_token),
+            ]),
+    )
+    .await?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let body = response.text().await?;
+        return Err(
+            format!("Request failed with status {}: {}", status, body).into()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_verification_qr_code(
+    device_auth: &DeviceAuthResponse,
+) -> Result<(), Box<dyn Error>> {
+    let code = QrCode::new(device_auth.verification_uri_complete.as_bytes())?;
+
+    // Colors are inverted since most terminals use a dark background, and QR
+    // codes need dark modules on a light background to scan reliably.
+    let image = code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build();
+
+    eprintln!(
+        "Scan the QR code below, or visit {} on any device and enter the code: {} to confirm your login.\n",
+        device_auth.verification_uri, device_auth.user_code
+    );
+    eprintln!("{}", image);
+    eprintln!("Code: {}\n", device_auth.user_code);
+
+    Ok(())
+}
+
+/// Sleeps for the provided duration. If `show_countdown` is true, the time
+/// remaining until the login request expires is updated in place every second
+/// while waiting.
+async fn wait_for_next_poll(
+    duration: Duration,
+    start_time: Instant,
+    expires_in: Duration,
+    show_countdown: bool,
+) {
+    if !show_countdown {
+        async_std::task::sleep(duration).await;
+
+        return;
+    }
+
+    let wake_time = Instant::now() + duration;
+
+    while Instant::now() < wake_time {
+        let remaining = expires_in.saturating_sub(start_time.elapsed());
+
+        eprint!(
+            "\rWaiting for authorization. The code expires in {:02}:{:02} ",
+            remaining.as_secs() / 60,
+            remaining.as_secs() % 60
+        );
+        let _ = stderr().flush();
+
+        let tick = wake_time
+            .saturating_duration_since(Instant::now())
+            .min(Duration::from_secs(1));
+        async_std::task::sleep(tick).await;
     }
 }
 
@@ -201,36 +747,77 @@ async fn poll_for_token<T: DeserializeOwned>(
     interval: u64,
     expires_in: u64,
     body: TokenRequest,
+    show_countdown: bool,
 ) -> Result<T, Box<dyn Error>> {
-    let interval = Duration::from_secs(interval);
+    let mut interval = Duration::from_secs(interval);
     let start_time = Instant::now();
     let expires_in = Duration::from_secs(expires_in);
 
-    let client = reqwest::Client::new();
+    let client = http_client::shared_client();
 
     loop {
         // spec says to wait for the interval before the first poll
-        async_std::task::sleep(interval).await;
+        wait_for_next_poll(interval, start_time, expires_in, show_countdown)
+            .await;
 
         if start_time.elapsed() >= expires_in {
             return Err("Parra sign in request has expired. Try again.".into());
         }
 
-        let response = client.post(url).json(&body).send().await?;
+        let response = http_client::send(client.post(url).json(&body)).await?;
         let status = response.status();
         let body = response.text().await?;
 
+        logging::log_response_body(&body);
+
         if status.is_success() {
             return Ok(serde_json::from_str::<T>(&body)?);
-        } else if status.as_u16() == 403 {
-            println!("Waiting for authorization from the browser...");
-        } else {
-            eprintln!(
-                "Check for authorization token failed unexpectedly {}: {}",
-                status, body
-            );
+        }
 
-            return Err("Request failed".into());
+        let error_response =
+            match serde_json::from_str::<OAuthErrorResponse>(&body) {
+                Ok(error_response) => error_response,
+                Err(_) => {
+                    eprintln!(
+                    "Check for authorization token failed unexpectedly {}: {}",
+                    status, body
+                );
+
+                    return Err("Request failed".into());
+                }
+            };
+
+        // https://datatracker.ietf.org/doc/html/rfc8628#section-3.5
+        match error_response.error {
+            OAuthError::AuthorizationPending => {
+                // The countdown already shows that we're waiting.
+                if !show_countdown {
+                    eprintln!("Waiting for authorization from the browser...");
+                }
+            }
+            OAuthError::SlowDown => {
+                // The spec requires increasing the interval by 5 seconds for
+                // this and all subsequent requests.
+                interval += Duration::from_secs(5);
+            }
+            OAuthError::AccessDenied => {
+                return Err("The login request was denied in the browser. Run the command again if this was a mistake.".into());
+            }
+            OAuthError::ExpiredToken => {
+                return Err(
+                    "Parra sign in request has expired. Try again.".into()
+                );
+            }
+            OAuthError::Other(code) => {
+                let description =
+                    error_response.error_description.unwrap_or(code);
+
+                return Err(format!(
+                    "Check for authorization token failed: {}",
+                    description
+                )
+                .into());
+            }
         }
     }
 }
@@ -0,0 +1,485 @@
+use crate::logging;
+use reqwest::{Request, Response, ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+const RECORD_ENV_VAR: &str = "PARRA_HTTP_RECORD";
+const REPLAY_ENV_VAR: &str = "PARRA_HTTP_REPLAY";
+
+/// The fields that hold credentials, which are the only parts of a recorded
+/// body that are redacted. Everything else is kept as it was received, so that
+/// replaying a cassette behaves like the original run.
+const CREDENTIAL_FIELDS: [&str; 7] = [
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "device_code",
+    "code_verifier",
+    "client_secret",
+    "password",
+];
+
+/// Credential fields that are only sent in form bodies to the authorization
+/// server. API resources can have JSON fields with these names too.
+const CREDENTIAL_FORM_FIELDS: [&str; 2] = ["code", "token"];
+
+const REDACTED: &str = "[REDACTED]";
+
+static MODE: OnceLock<Option<Mode>> = OnceLock::new();
+static NEXT_RECORDING: AtomicUsize = AtomicUsize::new(0);
+static REPLAYER: OnceLock<Replayer> = OnceLock::new();
+
+/// Whether requests are written to a cassette directory, or served from one
+/// instead of the network. Replaying takes precedence if both are set.
+enum Mode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A request and the response it received, stored as a numbered JSON file in
+/// the cassette directory. Credentials in the bodies are redacted, so cassettes
+/// can be attached to bug reports.
+#[derive(Debug, Deserialize, Serialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecordedRequest {
+    method: String,
+    url: String,
+    body: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+/// The interactions recorded in a cassette directory, each of which is served
+/// once.
+struct Replayer {
+    dir: PathBuf,
+    entries: Mutex<Vec<ReplayEntry>>,
+}
+
+struct ReplayEntry {
+    interaction: Interaction,
+    used: bool,
+}
+
+impl RecordedRequest {
+    pub fn from_request(request: &Request) -> Self {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| redact_credentials(&String::from_utf8_lossy(body)));
+
+        RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body,
+        }
+    }
+
+    /// Requests are matched on their path and query, so that a cassette
+    /// recorded against one profile can be replayed with another.
+    fn matches(&self, request: &Request) -> bool {
+        self.method == request.method().as_str()
+            && Url::parse(&self.url).is_ok_and(|url| {
+                path_and_query(&url) == path_and_query(request.url())
+            })
+    }
+}
+
+fn path_and_query(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Replaces the values of credential fields in a JSON or form body. Bodies
+/// without credentials are returned unchanged, byte for byte.
+fn redact_credentials(body: &str) -> String {
+    if let Ok(mut value) = serde_json::from_str::<Value>(body) {
+        if redact_json(&mut value) {
+            return value.to_string();
+        }
+
+        return body.to_string();
+    }
+
+    let is_form = !body.is_empty()
+        && !body.contains(char::is_whitespace)
+        && body.split('&').all(|pair| pair.contains('='));
+
+    if !is_form {
+        return body.to_string();
+    }
+
+    body.split('&')
+        .map(|pair| {
+            let (key, _) = pair.split_once('=').unwrap_or((pair, ""));
+
+            if CREDENTIAL_FIELDS.contains(&key)
+                || CREDENTIAL_FORM_FIELDS.contains(&key)
+            {
+                format!("{}={}", key, REDACTED)
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Returns whether any credential was redacted from the value.
+fn redact_json(value: &mut Value) -> bool {
+    match value {
+        Value::Object(map) => {
+            let mut redacted = false;
+
+            for (key, value) in map.iter_mut() {
+                if CREDENTIAL_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                    redacted = true;
+                } else {
+                    redacted |= redact_json(value);
+                }
+            }
+
+            redacted
+        }
+        Value::Array(values) => {
+            let mut redacted = false;
+
+            for value in values.iter_mut() {
+                redacted |= redact_json(value);
+            }
+
+            redacted
+        }
+        _ => false,
+    }
+}
+
+fn mode() -> &'static Option<Mode> {
+    MODE.get_or_init(|| {
+        if let Ok(dir) = env::var(REPLAY_ENV_VAR) {
+            return Some(Mode::Replay(PathBuf::from(dir)));
+        }
+
+        env::var(RECORD_ENV_VAR)
+            .ok()
+            .map(|dir| Mode::Record(PathBuf::from(dir)))
+    })
+}
+
+pub fn is_recording() -> bool {
+    matches!(mode(), Some(Mode::Record(_)))
+}
+
+pub fn is_replaying() -> bool {
+    matches!(mode(), Some(Mode::Replay(_)))
+}
+
+/// Writes the request and its response to the cassette directory. The body of
+/// the response has to be read in order to record it, so a new response with
+/// the same contents is returned in its place.
+pub async fn record(
+    request: RecordedRequest,
+    response: Response,
+) -> Result<Response, Box<dyn Error>> {
+    let dir = match mode() {
+        Some(Mode::Record(dir)) => dir,
+        _ => return Ok(response),
+    };
+
+    let status = response.status();
+    let url = response.url().clone();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+
+    // The length changes when secrets are redacted from the body, so it isn't
+    // recorded.
+    let recorded_headers = headers
+        .iter()
+        .filter(|(name, _)| {
+            !logging::is_secret_header(name.as_str())
+                && *name != reqwest::header::CONTENT_LENGTH
+        })
+        .filter_map(|(name, value)| {
+            Some((name.to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect();
+
+    let interaction = Interaction {
+        request,
+        response: RecordedResponse {
+            status: status.as_u16(),
+            headers: recorded_headers,
+            body: redact_credentials(&String::from_utf8_lossy(&body)),
+        },
+    };
+
+    fs::create_dir_all(dir)?;
+
+    // Numbering continues after any interactions already in the directory,
+    // so that recording more than one command keeps them in order.
+    if NEXT_RECORDING.load(Ordering::SeqCst) == 0 {
+        NEXT_RECORDING.store(count_interactions(dir)? + 1, Ordering::SeqCst);
+    }
+
+    let index = NEXT_RECORDING.fetch_add(1, Ordering::SeqCst);
+    fs::write(
+        dir.join(format!("{:04}.json", index)),
+        serde_json::to_string_pretty(&interaction)?,
+    )?;
+
+    let mut builder = http::Response::builder().status(status).url(url);
+
+    for (name, value) in headers.iter() {
+        builder = builder.header(name, value);
+    }
+
+    Ok(Response::from(builder.body(body)?))
+}
+
+/// Serves the response recorded for the request from the cassette directory.
+/// Each recorded interaction is used once, in the order it was recorded, so
+/// repeated requests such as token polling receive each of their responses in
+/// turn.
+pub fn replay(request: &Request) -> Result<Response, Box<dyn Error>> {
+    let dir = match mode() {
+        Some(Mode::Replay(dir)) => dir,
+        _ => return Err("HTTP replay is not enabled.".into()),
+    };
+
+    let replayer = match REPLAYER.get() {
+        Some(replayer) => replayer,
+        None => {
+            let replayer = Replayer::load(dir)?;
+
+            REPLAYER.get_or_init(|| replayer)
+        }
+    };
+
+    replayer.respond(request)
+}
+
+impl Replayer {
+    fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let entries = load_interactions(dir)?
+            .into_iter()
+            .map(|interaction| ReplayEntry {
+                interaction,
+                used: false,
+            })
+            .collect();
+
+        Ok(Replayer {
+            dir: dir.to_path_buf(),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    fn respond(&self, request: &Request) -> Result<Response, Box<dyn Error>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .iter_mut()
+            .find(|entry| {
+                !entry.used && entry.interaction.request.matches(request)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No recorded response for {} {} in {}.",
+                    request.method(),
+                    request.url(),
+                    self.dir.display()
+                )
+            })?;
+
+        entry.used = true;
+
+        let recorded = &entry.interaction.response;
+        let mut builder = http::Response::builder()
+            .status(recorded.status)
+            .url(request.url().clone());
+
+        for (name, value) in &recorded.headers {
+            builder = builder.header(name, value);
+        }
+
+        Ok(Response::from(builder.body(recorded.body.clone())?))
+    }
+}
+
+fn count_interactions(dir: &Path) -> Result<usize, Box<dyn Error>> {
+    Ok(interaction_paths(dir)?.len())
+}
+
+fn interaction_paths(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+
+    paths.sort();
+
+    Ok(paths)
+}
+
+fn load_interactions(dir: &Path) -> Result<Vec<Interaction>, Box<dyn Error>> {
+    let paths = interaction_paths(dir).map_err(|error| {
+        format!("Failed to read cassette {}: {}", dir.display(), error)
+    })?;
+
+    paths
+        .iter()
+        .map(|path| {
+            let data = fs::read_to_string(path)?;
+
+            serde_json::from_str::<Interaction>(&data).map_err(|error| {
+                format!("Invalid cassette file {}: {}", path.display(), error)
+                    .into()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redact_credentials, Replayer};
+    use serde_json::{json, Value};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Writes a cassette with the responses to the requests made while
+    /// listing workspaces.
+    fn write_cassette(dir: &Path) {
+        let interactions = [
+            ("http://replay.invalid/v1/user-info", json!({
+                "user": { "id": "user-1", "name": "Test User", "email": null }
+            })),
+            (
+                "http://replay.invalid/v1/users/user-1/tenants?%24top=100&%24skip=0",
+                json!({
+                    "data": [{
+                        "id": "tenant-1",
+                        "name": "Test Workspace",
+                        "subdomain": null,
+                        "logo": null,
+                        "domains": []
+                    }],
+                    "total_count": 1
+                }),
+            ),
+        ];
+
+        for (index, (url, body)) in interactions.iter().enumerate() {
+            let interaction = json!({
+                "request": { "method": "GET", "url": url, "body": null },
+                "response": {
+                    "status": 200,
+                    "headers": { "content-type": "application/json" },
+                    "body": body.to_string()
+                }
+            });
+
+            fs::write(
+                dir.join(format!("{:04}.json", index + 1)),
+                interaction.to_string(),
+            )
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses_without_the_network() {
+        let dir = TempDir::new().unwrap();
+        write_cassette(dir.path());
+
+        let replayer = Replayer::load(dir.path()).unwrap();
+        let client = reqwest::Client::new();
+
+        // Requests are matched on their path and query, so the host they
+        // were recorded against doesn't matter.
+        let user_info = client
+            .get("http://127.0.0.1:8080/v1/user-info")
+            .build()
+            .unwrap();
+        let tenants = client
+            .get("http://127.0.0.1:8080/v1/users/user-1/tenants")
+            .query(&[("$top", "100"), ("$skip", "0")])
+            .build()
+            .unwrap();
+
+        let user: Value =
+            replayer.respond(&user_info).unwrap().json().await.unwrap();
+        let page: Value =
+            replayer.respond(&tenants).unwrap().json().await.unwrap();
+
+        assert_eq!(user["user"]["id"], "user-1");
+        assert_eq!(page["data"][0]["name"], "Test Workspace");
+
+        // Each recorded response is only served once.
+        let error = replayer.respond(&tenants).unwrap_err();
+
+        assert!(error.to_string().contains("No recorded response"));
+    }
+
+    #[test]
+    fn keeps_bodies_without_credentials_verbatim() {
+        let body = "{\n  \"token\": \"page-2\",\n  \"code\": \"promo\"\n}";
+
+        assert_eq!(redact_credentials(body), body);
+        assert_eq!(
+            redact_credentials("<html>Not found</html>"),
+            "<html>Not found</html>"
+        );
+        assert_eq!(redact_credentials(""), "");
+    }
+
+    #[test]
+    fn redacts_credential_fields() {
+        let body = json!({
+            "token_type": "bearer",
+            "access_token": "secret",
+            "sessions": [{ "refresh_token": "secret" }],
+        });
+        let redacted: serde_json::Value =
+            serde_json::from_str(&redact_credentials(&body.to_string()))
+                .unwrap();
+
+        assert_eq!(
+            redacted,
+            json!({
+                "token_type": "bearer",
+                "access_token": "[REDACTED]",
+                "sessions": [{ "refresh_token": "[REDACTED]" }],
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_credential_form_fields() {
+        assert_eq!(
+            redact_credentials(
+                "grant_type=authorization_code&code=abc&redirect_uri=x"
+            ),
+            "grant_type=authorization_code&code=[REDACTED]&redirect_uri=x"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::api::{self, ApiSession};
+use crate::output::{self, Tabular};
+use crate::types::api::ApplicationResponse;
+use crate::validation;
+use std::error::Error;
+
+impl Tabular for ApplicationResponse {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Bundle ID", "Description", "Workspace ID"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            output::or_dash(
+                self.ios.as_ref().map(|ios| ios.bundle_id.as_str()),
+            ),
+            output::or_dash(self.description.as_deref()),
+            self.tenant_id.clone(),
+        ]
+    }
+}
+
+pub async fn execute_list(
+    workspace_id: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let session = ApiSession::new().await?;
+
+    let applications = match workspace_id {
+        Some(workspace_id) => {
+            api::paginate_applications(&session, &workspace_id)
+                .collect_all()
+                .await?
+        }
+        None => {
+            // Without a workspace, the applications in every workspace are
+            // listed.
+            let mut applications = vec![];
+            let mut tenants = api::paginate_tenants(&session);
+
+            while let Some(page) = tenants.next_page().await? {
+                for tenant in page {
+                    applications.extend(
+                        api::paginate_applications(&session, &tenant.id)
+                            .collect_all()
+                            .await?,
+                    );
+                }
+            }
+
+            applications
+        }
+    };
+
+    if applications.is_empty() && !output::is_structured() {
+        eprintln!(
+            "No applications found. Run `parra apps create` to create one."
+        );
+
+        return Ok(());
+    }
+
+    output::print_list(&applications)
+}
+
+pub async fn execute_show(
+    application_id: &str,
+    workspace_id: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let session = ApiSession::new().await?;
+    let application =
+        find_application(&session, application_id, workspace_id).await?;
+
+    output::print_item(&application)
+}
+
+pub async fn execute_create(
+    workspace_id: Option<String>,
+    name: &str,
+    bundle_id: &str,
+    description: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let name = name.trim();
+    let bundle_id = bundle_id.trim();
+
+    if name.is_empty() {
+        return Err("The application name can't be empty.".into());
+    }
+
+    validation::validate_bundle_id(bundle_id)?;
+
+    let session = ApiSession::new().await?;
+
+    let workspace_id = match workspace_id {
+        Some(workspace_id) => workspace_id,
+        None => only_workspace_id(&session).await?,
+    };
+
+    let application = api::create_application(
+        &session,
+        &workspace_id,
+        name,
+        bundle_id,
+        description.as_deref(),
+        false,
+    )
+    .await?;
+
+    eprintln!("Created application {}.", application);
+
+    output::print_item(&application)
+}
+
+pub async fn execute_update(
+    application_id: &str,
+    workspace_id: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+    bundle_id: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    if name.is_none() && description.is_none() && bundle_id.is_none() {
+        return Err("Nothing to update. Provide at least one of --name, --description or --bundle-id.".into());
+    }
+
+    if let Some(name) = &name {
+        if name.trim().is_empty() {
+            return Err("The application name can't be empty.".into());
+        }
+    }
+
+    if let Some(bundle_id) = &bundle_id {
+        validation::validate_bundle_id(bundle_id.trim())?;
+    }
+
+    let session = ApiSession::new().await?;
+    let application =
+        find_application(&session, application_id, workspace_id).await?;
+
+    let updated_application = api::update_application(
+        &session,
+        &application.tenant_id,
+        &application.id,
+        name.as_deref().map(str::trim),
+        description.as_deref(),
+        bundle_id.as_deref().map(str::trim),
+    )
+    .await?;
+
+    eprintln!("Updated application {}.", updated_application);
+
+    output::print_item(&updated_application)
+}
+
+pub async fn execute_delete(
+    application_id: &str,
+    workspace_id: Option<String>,
+    confirm: &str,
+) -> Result<(), Box<dyn Error>> {
+    let session = ApiSession::new().await?;
+    let application =
+        find_application(&session, application_id, workspace_id).await?;
+
+    // Deleting can't be undone, so the name has to be provided to make sure
+    // the ID refers to the intended application.
+    if confirm.trim() != application.name {
+        return Err(format!(
+            "The confirmation doesn't match the application's name. To delete {}, pass --confirm \"{}\".",
+            application, application.name
+        )
+        .into());
+    }
+
+    api::delete_application(&session, &application.tenant_id, &application.id)
+        .await?;
+
+    eprintln!("Deleted application {}.", application);
+
+    Ok(())
+}
+
+/// When a workspace isn't provided, the user's workspace is used if they only
+/// have one. Otherwise it is ambiguous which one is meant.
+async fn only_workspace_id(
+    session: &ApiSession,
+) -> Result<String, Box<dyn Error>> {
+    let tenants = api::paginate_tenants(session).collect_all().await?;
+
+    match tenants.as_slice() {
+        [tenant] => Ok(tenant.id.clone()),
+        [] => Err("You don't have any workspaces. Run `parra workspaces create <name>` to create one.".into()),
+        _ => Err("You have more than one workspace. Choose one with --workspace. Run `parra workspaces list` to see them.".into()),
+    }
+}
+
+/// Gets the application from the workspace if one is provided. Otherwise,
+/// every workspace the user has access to is searched for it.
+async fn find_application(
+    session: &ApiSession,
+    application_id: &str,
+    workspace_id: Option<String>,
+) -> Result<ApplicationResponse, Box<dyn Error>> {
+    if let Some(workspace_id) = workspace_id {
+        return api::get_application(session, &workspace_id, application_id)
+            .await;
+    }
+
+    let mut tenants = api::paginate_tenants(session);
+
+    while let Some(page) = tenants.next_page().await? {
+        for tenant in page {
+            let mut applications =
+                api::paginate_applications(session, &tenant.id);
+
+            while let Some(page) = applications.next_page().await? {
+                if let Some(application) = page
+                    .into_iter()
+                    .find(|application| application.id == application_id)
+                {
+                    return Ok(application);
+                }
+            }
+        }
+    }
+
+    Err(format!(
+        "No application with ID {} was found in any of your workspaces.",
+        application_id
+    )
+    .into())
+}
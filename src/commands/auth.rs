@@ -0,0 +1,287 @@
+use crate::accounts::Account;
+use crate::api::ApiSession;
+use crate::arg_parser::LoginMethod;
+use crate::output::{self, Tabular};
+use crate::{accounts, auth, config, jwt};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize)]
+struct AuthStatus {
+    profile: String,
+    /// Missing when the credential was provided through the environment.
+    account: Option<Account>,
+    /// Unix timestamp of when the access token expires, if known.
+    expires_at: Option<u64>,
+    /// Seconds until the access token expires. Negative once it has expired.
+    expires_in: Option<i64>,
+    scopes: Vec<String>,
+    /// The unverified claims from the access token, if it is a JWT.
+    claims: Option<Map<String, Value>>,
+}
+
+impl Tabular for AuthStatus {
+    fn headers() -> Vec<&'static str> {
+        vec!["Profile", "Account", "Token", "Scopes"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.profile.clone(),
+            match &self.account {
+                Some(account) => format!("{} ({})", account.name, account.id),
+                None => "- (credential from environment)".to_string(),
+            },
+            describe_expiry(self.expires_at),
+            if self.scopes.is_empty() {
+                "-".to_string()
+            } else {
+                self.scopes.join(" ")
+            },
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WhoamiResult {
+    id: String,
+    name: String,
+    email: Option<String>,
+    /// Unix timestamp of when the access token expires, if known.
+    expires_at: Option<u64>,
+}
+
+impl Tabular for WhoamiResult {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Email", "Token"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            output::or_dash(self.email.as_deref()),
+            describe_expiry(self.expires_at),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AccountEntry {
+    active: bool,
+    #[serde(flatten)]
+    account: Account,
+}
+
+impl Tabular for AccountEntry {
+    fn headers() -> Vec<&'static str> {
+        vec!["", "ID", "Name", "Email"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            if self.active { "*" } else { "" }.to_string(),
+            self.account.id.clone(),
+            self.account.name.clone(),
+            output::or_dash(self.account.email.as_deref()),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TokenResult {
+    access_token: String,
+    expires_at: Option<u64>,
+}
+
+pub async fn execute_login(
+    method: LoginMethod,
+    no_browser: bool,
+) -> Result<(), Box<dyn Error>> {
+    // The user is the one the new credential belongs to, which isn't
+    // necessarily the one an `ApiSession` would pick with `--account` or a
+    // credential in the environment.
+    let user = match method {
+        LoginMethod::Device => {
+            auth::perform_normal_authentication(!no_browser).await?.user
+        }
+        LoginMethod::Browser => {
+            if no_browser {
+                return Err(
+                    "The browser login method can't be used with --no-browser."
+                        .into(),
+                );
+            }
+
+            auth::perform_authorization_code_authentication()
+                .await?
+                .user
+        }
+    };
+
+    match user.email {
+        Some(email) => eprintln!("Logged in as {} ({})", user.name, email),
+        None => eprintln!("Logged in as {}", user.name),
+    }
+
+    Ok(())
+}
+
+pub async fn execute_logout() -> Result<(), Box<dyn Error>> {
+    let had_credential = auth::has_credential();
+
+    match auth::perform_logout().await? {
+        Some(account) => eprintln!("Logged out of {}.", account.name),
+        None if had_credential => eprintln!("Logged out successfully."),
+        None => eprintln!("You are not logged in."),
+    }
+
+    Ok(())
+}
+
+pub async fn execute_whoami() -> Result<(), Box<dyn Error>> {
+    // Don't trigger the login flow if there isn't a credential available. This
+    // command is meant to be used to check the current state.
+    if !auth::has_credential() {
+        return Err("You are not logged in. Run `parra login` first.".into());
+    }
+
+    let authorized_user = ApiSession::new().await?.authorized_user();
+    let user = authorized_user.user;
+
+    output::print_item(&WhoamiResult {
+        id: user.id,
+        name: user.name,
+        email: user.email,
+        expires_at: known_expiry(authorized_user.credential.expiry),
+    })
+}
+
+pub fn execute_list() -> Result<(), Box<dyn Error>> {
+    let (accounts, active) = accounts::list_accounts()?;
+
+    if accounts.is_empty() && !output::is_structured() {
+        eprintln!("No accounts are signed in. Run `parra login` to add one.");
+
+        return Ok(());
+    }
+
+    let entries: Vec<AccountEntry> = accounts
+        .into_iter()
+        .map(|account| AccountEntry {
+            active: active.as_deref() == Some(account.id.as_str()),
+            account,
+        })
+        .collect();
+
+    output::print_list(&entries)
+}
+
+pub fn execute_switch(user: &str) -> Result<(), Box<dyn Error>> {
+    let account = accounts::find_account(user)?;
+
+    accounts::set_active_account(&account.id)?;
+
+    eprintln!("Switched to {}.", account.name);
+
+    Ok(())
+}
+
+/// Prints the access token for the current account, refreshing it first if it
+/// is about to expire, so that it can be used to call the API from scripts.
+pub async fn execute_token() -> Result<(), Box<dyn Error>> {
+    if !auth::has_credential() {
+        return Err("You are not logged in. Run `parra login` first.".into());
+    }
+
+    let credential = auth::perform_device_authentication().await?;
+
+    // The token is printed on its own by default, so that it can be used
+    // directly in scripts.
+    if !output::is_structured() {
+        return output::write_lines(&[credential.token]);
+    }
+
+    output::print_structured(&TokenResult {
+        expires_at: known_expiry(credential.expiry),
+        access_token: credential.token,
+    })
+}
+
+pub async fn execute_status(json: bool) -> Result<(), Box<dyn Error>> {
+    let credential = auth::get_current_credential()
+        .await?
+        .ok_or("You are not logged in. Run `parra login` first.")?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let claims = jwt::decode_claims(&credential.token).ok();
+    let expires_at = known_expiry(credential.expiry);
+
+    let scope = credential.scope.clone().or_else(|| {
+        claims
+            .as_ref()
+            .and_then(|claims| claims.get("scope"))
+            .and_then(|scope| scope.as_str())
+            .map(|scope| scope.to_string())
+    });
+
+    let status = AuthStatus {
+        profile: config::active_profile().name.clone(),
+        account: if auth::has_environment_credential() {
+            None
+        } else {
+            accounts::current_account()?
+        },
+        expires_at,
+        expires_in: expires_at.map(|expiry| expiry as i64 - now as i64),
+        scopes: scope
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|scope| scope.to_string())
+            .collect(),
+        claims,
+    };
+
+    // --json predates the --output flag, and is kept for compatibility.
+    if json {
+        return output::write_lines(&[serde_json::to_string_pretty(&status)?]);
+    }
+
+    output::print_item(&status)
+}
+
+/// Credentials whose expiry isn't known are stored with the maximum expiry.
+fn known_expiry(expiry: u64) -> Option<u64> {
+    if expiry == u64::MAX {
+        None
+    } else {
+        Some(expiry)
+    }
+}
+
+fn describe_expiry(expires_at: Option<u64>) -> String {
+    let expiry = match expires_at {
+        Some(expiry) => expiry,
+        None => return "expiry unknown".to_string(),
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    if expiry <= now {
+        return format!("expired (at {})", expiry);
+    }
+
+    let remaining = expiry - now;
+
+    format!(
+        "expires in {}m {}s (at {})",
+        remaining / 60,
+        remaining % 60,
+        expiry
+    )
+}
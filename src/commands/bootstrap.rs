@@ -1,14 +1,21 @@
+use crate::api::{self, ApiSession};
 use crate::dependencies::DerivedDependency;
-use crate::types::api::{ApplicationResponse, TenantResponse};
+use crate::project_generator::generator;
+use crate::prompt::{
+    Answers, AnswersPrompter, Choice, NonInteractivePrompter, Prompter,
+    Question, TerminalPrompter,
+};
+use crate::types::api::{
+    ApplicationIosConfig, ApplicationResponse, ApplicationType, TenantResponse,
+};
 use crate::types::dependency::XcodeVersion;
-use crate::{api, dependencies, project_generator};
+use crate::{dependencies, output, validation};
 use convert_case::{Case, Casing};
-use inquire::validator::{MaxLengthValidator, MinLengthValidator, Validation};
-use inquire::{Confirm, InquireError, Select, Text};
-use regex::Regex;
+use serde::Serialize;
 use slugify::slugify;
 use std::error::Error;
 use std::fmt::Display;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{exit, Command};
 use std::str::FromStr;
@@ -25,6 +32,9 @@ static DESIRED_XCODE_VERSION: XcodeVersion = XcodeVersion {
     patch: 0,
 };
 
+/// The ID shown for a workspace or application that a dry run would create.
+const NOT_CREATED_ID: &str = "(not created)";
+
 impl Display for TenantResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} ({})", self.name, self.id)
@@ -47,40 +57,309 @@ impl Display for XcodeVersion {
     }
 }
 
+/// The keys of the questions that can be answered in an answers file.
+const ANSWER_KEYS: [&str; 10] = [
+    "use_existing_workspace",
+    "workspace",
+    "workspace_name",
+    "use_existing_application",
+    "application",
+    "app_name",
+    "bundle_id",
+    "project_path",
+    "overwrite",
+    "install_deps",
+];
+
+/// The keys of the answers that choose between an existing workspace or
+/// application, and creating a new one.
+struct ChoiceAnswers {
+    use_existing: &'static str,
+    existing: &'static str,
+    new: &'static str,
+}
+
+const WORKSPACE_ANSWERS: ChoiceAnswers = ChoiceAnswers {
+    use_existing: "use_existing_workspace",
+    existing: "workspace",
+    new: "workspace_name",
+};
+
+const APPLICATION_ANSWERS: ChoiceAnswers = ChoiceAnswers {
+    use_existing: "use_existing_application",
+    existing: "application",
+    new: "app_name",
+};
+
+/// The answers to the prompts of `bootstrap` that were provided as options.
+/// Anything not provided is read from the answers file if there is one, and
+/// otherwise prompted for, unless `non_interactive` is set.
+pub struct BootstrapOptions {
+    pub application_id: Option<String>,
+    pub workspace_id: Option<String>,
+    pub project_path: Option<String>,
+    pub app_name: Option<String>,
+    pub bundle_id: Option<String>,
+    pub workspace_name: Option<String>,
+    pub answers: Option<String>,
+    pub overwrite: bool,
+    pub install_deps: bool,
+    pub yes: bool,
+    pub non_interactive: bool,
+    pub dry_run: bool,
+    pub scratch_dir: Option<String>,
+}
+
+/// The API calls made while resolving the workspace and application. During a
+/// dry run, the calls that would make changes are recorded without being sent.
+struct Plan {
+    dry_run: bool,
+    api_calls: Vec<PlannedApiCall>,
+}
+
+#[derive(Serialize)]
+struct PlannedApiCall {
+    method: &'static str,
+    path: String,
+    description: String,
+    sent: bool,
+}
+
+impl Plan {
+    fn read(&mut self, path: String, description: &str) {
+        self.api_calls.push(PlannedApiCall {
+            method: "GET",
+            path,
+            description: description.to_string(),
+            sent: true,
+        });
+    }
+
+    fn write(&mut self, path: String, description: String) {
+        self.api_calls.push(PlannedApiCall {
+            method: "POST",
+            path,
+            description,
+            sent: !self.dry_run,
+        });
+    }
+}
+
+/// Everything `bootstrap` would do, printed by a dry run.
+#[derive(Serialize)]
+struct DryRunResult {
+    project_dir: String,
+    replaces_existing: bool,
+    api_calls: Vec<PlannedApiCall>,
+    missing_dependencies: Vec<MissingDependency>,
+    directories: Vec<String>,
+    files: Vec<String>,
+    commands: Vec<String>,
+}
+
+/// A dependency that isn't installed, and what a real run would do about it.
+#[derive(Serialize)]
+struct MissingDependency {
+    name: String,
+    action: InstallAction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum InstallAction {
+    Install,
+    Skip,
+    Ask,
+    NeedsInstallDeps,
+}
+
+impl Display for InstallAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallAction::Install => write!(f, "will be installed"),
+            InstallAction::Skip => write!(f, "won't be installed"),
+            InstallAction::Ask => write!(f, "would ask to install"),
+            InstallAction::NeedsInstallDeps => {
+                write!(f, "would need --install-deps")
+            }
+        }
+    }
+}
+
+/// The API calls made by the wizard, so that it can be run against data other
+/// than the Parra API's.
+trait WizardApi {
+    fn user_id(&self) -> String;
+
+    async fn get_tenant(
+        &self,
+        tenant_id: &str,
+    ) -> Result<TenantResponse, Box<dyn Error>>;
+
+    async fn list_tenants(&self)
+        -> Result<Vec<TenantResponse>, Box<dyn Error>>;
+
+    async fn create_tenant(
+        &self,
+        name: &str,
+    ) -> Result<TenantResponse, Box<dyn Error>>;
+
+    async fn get_application(
+        &self,
+        tenant_id: &str,
+        application_id: &str,
+    ) -> Result<ApplicationResponse, Box<dyn Error>>;
+
+    async fn list_applications(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<ApplicationResponse>, Box<dyn Error>>;
+
+    async fn create_application(
+        &self,
+        tenant_id: &str,
+        name: &str,
+        bundle_id: &str,
+    ) -> Result<ApplicationResponse, Box<dyn Error>>;
+}
+
+impl WizardApi for ApiSession {
+    fn user_id(&self) -> String {
+        ApiSession::user_id(self)
+    }
+
+    async fn get_tenant(
+        &self,
+        tenant_id: &str,
+    ) -> Result<TenantResponse, Box<dyn Error>> {
+        api::get_tenant(self, tenant_id).await
+    }
+
+    async fn list_tenants(
+        &self,
+    ) -> Result<Vec<TenantResponse>, Box<dyn Error>> {
+        api::paginate_tenants(self).collect_all().await
+    }
+
+    async fn create_tenant(
+        &self,
+        name: &str,
+    ) -> Result<TenantResponse, Box<dyn Error>> {
+        api::create_tenant(self, name, false).await
+    }
+
+    async fn get_application(
+        &self,
+        tenant_id: &str,
+        application_id: &str,
+    ) -> Result<ApplicationResponse, Box<dyn Error>> {
+        api::get_application(self, tenant_id, application_id).await
+    }
+
+    async fn list_applications(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Vec<ApplicationResponse>, Box<dyn Error>> {
+        api::paginate_applications(self, tenant_id)
+            .collect_all()
+            .await
+    }
+
+    async fn create_application(
+        &self,
+        tenant_id: &str,
+        name: &str,
+        bundle_id: &str,
+    ) -> Result<ApplicationResponse, Box<dyn Error>> {
+        api::create_application(self, tenant_id, name, bundle_id, None, true)
+            .await
+    }
+}
+
 pub async fn execute_bootstrap(
-    application_id: Option<String>,
-    workspace_id: Option<String>,
-    project_path: Option<String>,
+    options: BootstrapOptions,
 ) -> Result<(), Box<dyn Error>> {
-    let tenant = get_tenant(workspace_id).await?;
-    let mut application = get_application(application_id, &tenant).await?;
+    if let Some(bundle_id) = &options.bundle_id {
+        validation::validate_bundle_id(bundle_id.trim())
+            .map_err(|message| format!("Invalid --bundle-id: {}", message))?;
+    }
 
-    // If the app name ends with "App", remove it.
-    if application.name.to_lowercase().ends_with("app") {
-        application.name =
-            application.name.trim_end_matches("app").trim().to_string();
+    let mut answers = options
+        .answers
+        .as_ref()
+        .map(|path| Answers::load(Path::new(path), &ANSWER_KEYS))
+        .transpose()?;
+
+    if let Some(answers) = answers.as_mut() {
+        imply_choices(answers);
     }
 
+    if options.non_interactive {
+        check_non_interactive_inputs(&options, answers.as_ref())?;
+    }
+
+    let install_deps_answer = answers
+        .as_ref()
+        .and_then(|answers| answers.flag("install_deps"));
+
+    let fallback: Box<dyn Prompter> = if options.non_interactive {
+        Box::new(NonInteractivePrompter)
+    } else {
+        Box::new(TerminalPrompter)
+    };
+
+    let mut prompter: Box<dyn Prompter> = match answers {
+        Some(answers) => Box::new(AnswersPrompter::new(answers, fallback)),
+        None => fallback,
+    };
+    let prompter = prompter.as_mut();
+
+    let session = ApiSession::new().await?;
+    let mut plan = Plan {
+        dry_run: options.dry_run,
+        api_calls: Vec::new(),
+    };
+    plan.read("/user-info".to_string(), "Get the signed in user");
+
+    let tenant = get_tenant(&session, prompter, &mut plan, &options).await?;
+    let mut application =
+        get_application(&session, prompter, &mut plan, &options, &tenant)
+            .await?;
+
+    application.name = project_name(&application.name);
+
     let kebab_name = application.name.to_case(Case::Kebab);
-    let relative_path = get_project_path(project_path, &kebab_name);
+    let relative_path =
+        get_project_path(prompter, options.project_path.clone(), &kebab_name)?;
+
+    let expanded_path = project_dir(&relative_path, &kebab_name)?;
 
-    let mut project_path = PathBuf::from_str(&relative_path)?;
-    if !project_path.ends_with(&kebab_name) {
-        project_path.push(&kebab_name);
+    // A dry run only reports that the directory would be replaced.
+    let replaces_existing = expanded_path.exists();
+    if replaces_existing && !options.dry_run {
+        confirm_overwrite(prompter, &options)?;
     }
-    let expanded_path = expand_tilde(&project_path).unwrap();
 
-    println!("Generating project...");
+    if options.dry_run {
+        return print_dry_run(
+            plan,
+            &expanded_path,
+            replaces_existing,
+            tenant,
+            application,
+            &options,
+            install_deps_answer,
+        );
+    }
 
-    let xcode_project = project_generator::generator::generate_xcode_project(
-        &expanded_path,
-        tenant,
-        application,
-    )?;
+    eprintln!("Generating project...");
+
+    let xcode_project =
+        generator::generate_xcode_project(&expanded_path, tenant, application)?;
 
     let xcode_target_dir = &xcode_project;
 
-    println!(
+    eprintln!(
         "Parra project generated at {}!",
         expanded_path.to_str().unwrap()
     );
@@ -91,7 +370,7 @@ pub async fn execute_bootstrap(
     if missing.is_empty() {
         open_project(xcode_target_dir)?;
     } else {
-        dependencies(missing).await?;
+        dependencies(prompter, missing, &options).await?;
 
         open_project(xcode_target_dir)?;
     }
@@ -99,12 +378,289 @@ pub async fn execute_bootstrap(
     Ok(())
 }
 
+/// Choosing a workspace or application in the answers file also answers
+/// whether to use an existing one, and naming a new one answers it too.
+fn imply_choices(answers: &mut Answers) {
+    for choice in [WORKSPACE_ANSWERS, APPLICATION_ANSWERS] {
+        if answers.contains(choice.existing) {
+            answers.imply(choice.use_existing, true);
+        } else if answers.contains(choice.new) {
+            answers.imply(choice.use_existing, false);
+        }
+    }
+}
+
+/// Fails with every option that would otherwise have to be prompted for, so
+/// that they can all be fixed at once. Values with a default, like the project
+/// path and bundle ID, use it instead of being required.
+fn check_non_interactive_inputs(
+    options: &BootstrapOptions,
+    answers: Option<&Answers>,
+) -> Result<(), Box<dyn Error>> {
+    let answered = |keys: &[&str]| {
+        answers
+            .is_some_and(|answers| keys.iter().any(|key| answers.contains(key)))
+    };
+
+    // Whether the answers file picks an existing workspace or application, or
+    // names a new one, in the way the wizard would follow it. `--yes` always
+    // uses an existing one.
+    let chosen = |choice: &ChoiceAnswers| {
+        answers.and_then(|answers| {
+            let use_existing = if options.yes {
+                Some(true)
+            } else {
+                answers.flag(choice.use_existing)
+            };
+
+            match use_existing {
+                Some(true) if answers.contains(choice.existing) => Some(true),
+                Some(false) if answers.contains(choice.new) => Some(false),
+                _ => None,
+            }
+        })
+    };
+
+    let mut missing = Vec::<String>::new();
+
+    if options.workspace_id.is_none()
+        && options.workspace_name.is_none()
+        && chosen(&WORKSPACE_ANSWERS).is_none()
+    {
+        missing.push("--workspace-id or --workspace-name".to_string());
+    }
+
+    let application = chosen(&APPLICATION_ANSWERS);
+    if options.application_id.is_none()
+        && options.app_name.is_none()
+        && application.is_none()
+    {
+        missing.push("--application-id or --app-name".to_string());
+    }
+
+    // The project directory is only known up front when the application is
+    // chosen by name. Otherwise, it is checked once the application is known.
+    let app_name = options.app_name.as_deref().or_else(|| {
+        answers
+            .filter(|_| application == Some(false))
+            .and_then(|answers| answers.text("app_name"))
+    });
+
+    if let Some(app_name) = app_name {
+        let kebab_name = project_name(app_name.trim()).to_case(Case::Kebab);
+        let relative_path = options
+            .project_path
+            .clone()
+            .or_else(|| {
+                answers
+                    .and_then(|answers| answers.text("project_path"))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| format!("./{}", kebab_name));
+        let project_dir = project_dir(&relative_path, &kebab_name)?;
+
+        if project_dir.exists()
+            && !options.dry_run
+            && !options.overwrite
+            && !answered(&["overwrite"])
+        {
+            missing.push(format!(
+                "--overwrite (the project directory {} already exists)",
+                project_dir.display()
+            ));
+        }
+    }
+
+    // A dry run lists the missing dependencies in its plan instead.
+    if !options.dry_run
+        && !options.install_deps
+        && !answered(&["install_deps"])
+        && !missing_dependencies().is_empty()
+    {
+        missing.push(
+            "--install-deps (dependencies need to be installed)".to_string(),
+        );
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Missing required options for --non-interactive:\n  {}",
+        missing.join("\n  ")
+    )
+    .into())
+}
+
+/// Renders the project without writing it, and prints what a real run would
+/// do. The rendered files are written to the scratch directory if there is
+/// one, so that they can be compared with an existing project.
+fn print_dry_run(
+    plan: Plan,
+    project_dir: &Path,
+    replaces_existing: bool,
+    tenant: TenantResponse,
+    application: ApplicationResponse,
+    options: &BootstrapOptions,
+    install_deps_answer: Option<bool>,
+) -> Result<(), Box<dyn Error>> {
+    let project = generator::render_project(tenant, application)?;
+
+    if let Some(scratch_dir) = &options.scratch_dir {
+        let scratch_dir = expand_tilde(scratch_dir).unwrap();
+
+        generator::write_project(&scratch_dir, &project)?;
+        fs::write(
+            scratch_dir.join("parra_project.yml"),
+            &project.project_yaml,
+        )?;
+
+        eprintln!("Rendered files written to {}.", scratch_dir.display());
+    }
+
+    let install_action = match install_deps_answer {
+        _ if options.install_deps => InstallAction::Install,
+        Some(true) => InstallAction::Install,
+        Some(false) => InstallAction::Skip,
+        None if options.non_interactive => InstallAction::NeedsInstallDeps,
+        None => InstallAction::Ask,
+    };
+
+    let result = dry_run_result(
+        plan,
+        project_dir,
+        replaces_existing,
+        &project,
+        &missing_dependencies(),
+        install_action,
+    );
+
+    eprintln!("Dry run: nothing was created, written or installed.");
+
+    if output::is_structured() {
+        return output::print_structured(&result);
+    }
+
+    let mut lines = vec![if result.replaces_existing {
+        format!("Replaces the existing project at {}.", result.project_dir)
+    } else {
+        format!("Creates the project at {}.", result.project_dir)
+    }];
+
+    lines.push("\nAPI calls:".to_string());
+    for call in &result.api_calls {
+        let note = if call.sent { "" } else { " (not sent)" };
+        lines.push(format!(
+            "  {} {} - {}{}",
+            call.method, call.path, call.description, note
+        ));
+    }
+
+    if !result.missing_dependencies.is_empty() {
+        lines.push("\nMissing dependencies:".to_string());
+        lines.extend(result.missing_dependencies.iter().map(|dependency| {
+            format!("  {} ({})", dependency.name, dependency.action)
+        }));
+    }
+
+    let sections = [
+        ("Directories", &result.directories),
+        ("Files", &result.files),
+        ("Commands", &result.commands),
+    ];
+    for (title, items) in sections {
+        lines.push(format!("\n{}:", title));
+        lines.extend(items.iter().map(|item| format!("  {}", item)));
+    }
+
+    output::write_lines(&lines)
+}
+
+/// The plan printed by a dry run. Commands to install the missing
+/// dependencies are included unless a real run wouldn't install them.
+fn dry_run_result(
+    plan: Plan,
+    project_dir: &Path,
+    replaces_existing: bool,
+    project: &generator::RenderedProject,
+    missing: &[DerivedDependency],
+    install_action: InstallAction,
+) -> DryRunResult {
+    let mut commands = generator::generation_commands(project_dir);
+    if !missing.is_empty() && install_action != InstallAction::Skip {
+        commands.extend(dependencies::install_commands(DESIRED_XCODE_VERSION));
+    }
+    commands.push(open_project_command(&project_dir.join(&project.target_dir)));
+
+    let mut files: Vec<String> = project
+        .files
+        .iter()
+        .map(|file| project_dir.join(&file.path).display().to_string())
+        .collect();
+    files.push(generator::XCODEGEN_SPEC_PATH.to_string());
+
+    DryRunResult {
+        project_dir: project_dir.display().to_string(),
+        replaces_existing,
+        api_calls: plan.api_calls,
+        missing_dependencies: missing
+            .iter()
+            .map(|dependency| MissingDependency {
+                name: match dependency {
+                    DerivedDependency::Xcode => {
+                        format!("Xcode {} or later", MIN_XCODE_VERSION)
+                    }
+                },
+                action: install_action,
+            })
+            .collect(),
+        directories: project
+            .directories
+            .iter()
+            .map(|directory| project_dir.join(directory).display().to_string())
+            .collect(),
+        files,
+        commands: commands
+            .iter()
+            .map(dependencies::describe_command)
+            .collect(),
+    }
+}
+
+fn confirm_overwrite(
+    prompter: &mut dyn Prompter,
+    options: &BootstrapOptions,
+) -> Result<(), Box<dyn Error>> {
+    if options.overwrite {
+        return Ok(());
+    }
+
+    let result = prompter.confirm(
+        &Question {
+            key: "overwrite",
+            message: "Project directory already exists. Overwrite?",
+            help: Some("If you choose not to proceed, the program will exit."),
+            option: Some("--overwrite"),
+        },
+        false,
+    )?;
+
+    if !result {
+        exit(1);
+    }
+
+    Ok(())
+}
+
 fn missing_dependencies() -> Vec<DerivedDependency> {
     dependencies::check_for_missing_dependencies(MIN_XCODE_VERSION)
 }
 
 async fn dependencies(
+    prompter: &mut dyn Prompter,
     missing: Vec<DerivedDependency>,
+    options: &BootstrapOptions,
 ) -> Result<(), Box<dyn Error>> {
     if missing.is_empty() {
         return Ok(());
@@ -118,14 +674,22 @@ async fn dependencies(
         "We need to install a few dependencies first. Proceed?"
     };
 
-    let confirmed_install =
-        Confirm::new(confirm_message).with_default(true).prompt()?;
+    let confirmed_install = options.install_deps
+        || prompter.confirm(
+            &Question {
+                key: "install_deps",
+                message: confirm_message,
+                help: None,
+                option: Some("--install-deps"),
+            },
+            true,
+        )?;
 
     // Trim the input and check if it's an affirmative response
     if confirmed_install {
         dependencies::install_missing_dependencies(DESIRED_XCODE_VERSION);
     } else {
-        println!("Please install Xcode version {} or later before opening your new project.", MIN_XCODE_VERSION);
+        eprintln!("Please install Xcode version {} or later before opening your new project.", MIN_XCODE_VERSION);
 
         exit(1)
     }
@@ -134,143 +698,370 @@ async fn dependencies(
 }
 
 async fn get_tenant(
-    tenant_arg: Option<String>,
+    session: &impl WizardApi,
+    prompter: &mut dyn Prompter,
+    plan: &mut Plan,
+    options: &BootstrapOptions,
 ) -> Result<TenantResponse, Box<dyn Error>> {
     // The user provided a tenant ID directly.
-    if let Some(tenant_id) = tenant_arg {
-        return api::get_tenant(&tenant_id).await;
+    if let Some(tenant_id) = &options.workspace_id {
+        plan.read(format!("/tenants/{}", tenant_id), "Get the workspace");
+
+        return session.get_tenant(tenant_id).await;
     }
 
-    let tenants = api::get_tenants().await?;
+    plan.read(
+        format!("/users/{}/tenants", session.user_id()),
+        "List workspaces",
+    );
+    let mut tenants = session.list_tenants().await?;
+
+    // The user provided a name, which refers to an existing workspace if one
+    // has it, or else the one to create.
+    if let Some(name) = &options.workspace_name {
+        let name = name.trim();
+
+        if let Some(tenant) =
+            tenants.into_iter().find(|tenant| tenant.name == name)
+        {
+            return Ok(tenant);
+        }
+
+        return create_tenant(session, plan, name).await;
+    }
 
     if tenants.is_empty() {
-        return create_new_tenant().await;
+        return create_new_tenant(session, prompter, plan).await;
     }
 
-    let use_existing =
-        Confirm::new("Would you like to use an existing workspace?")
-            .with_default(true)
-            .prompt()?;
+    let use_existing = options.yes
+        || prompter.confirm(
+            &Question {
+                key: "use_existing_workspace",
+                message: "Would you like to use an existing workspace?",
+                help: None,
+                option: None,
+            },
+            true,
+        )?;
 
     if use_existing {
-        let selected_tenant: Result<TenantResponse, InquireError> =
-            Select::new("Please select a workspace", tenants).prompt();
+        let choices: Vec<Choice> = tenants
+            .iter()
+            .map(|tenant| Choice {
+                id: tenant.id.clone(),
+                name: tenant.name.clone(),
+                label: tenant.to_string(),
+            })
+            .collect();
+
+        let index = prompter.select(
+            &Question {
+                key: "workspace",
+                message: "Please select a workspace",
+                help: None,
+                option: Some("--workspace-id"),
+            },
+            &choices,
+        )?;
 
-        return Ok(selected_tenant?);
+        Ok(tenants.swap_remove(index))
     } else {
-        return create_new_tenant().await;
+        create_new_tenant(session, prompter, plan).await
     }
 }
 
 async fn get_application(
-    application_arg: Option<String>,
+    session: &impl WizardApi,
+    prompter: &mut dyn Prompter,
+    plan: &mut Plan,
+    options: &BootstrapOptions,
     tenant: &TenantResponse,
 ) -> Result<ApplicationResponse, Box<dyn Error>> {
     // The user provided a application ID directly.
-    if let Some(application_arg) = application_arg {
-        return api::get_application(&tenant.id, &application_arg).await;
+    if let Some(application_arg) = &options.application_id {
+        plan.read(
+            format!("/tenants/{}/applications/{}", tenant.id, application_arg),
+            "Get the application",
+        );
+
+        return session.get_application(&tenant.id, application_arg).await;
     }
 
-    let applications = api::paginate_applications(&tenant.id).await?;
+    // A workspace that a dry run would create doesn't have any applications
+    // yet.
+    let mut applications = if tenant.id == NOT_CREATED_ID {
+        Vec::new()
+    } else {
+        plan.read(
+            format!("/tenants/{}/applications", tenant.id),
+            "List applications",
+        );
+
+        session.list_applications(&tenant.id).await?
+    };
+
+    // The user provided a name, which refers to an existing application in
+    // the workspace if one has it, or else the one to create.
+    if let Some(name) = &options.app_name {
+        let name = name.trim();
+
+        if let Some(application) = applications
+            .into_iter()
+            .find(|application| application.name == name)
+        {
+            warn_unused_bundle_id(options, &application);
+
+            return Ok(application);
+        }
+
+        return create_new_application(
+            session, prompter, plan, tenant, name, options,
+        )
+        .await;
+    }
 
     if applications.is_empty() {
-        return create_new_application(&tenant).await;
+        let name = prompt_application_name(prompter)?;
+
+        return create_new_application(
+            session, prompter, plan, tenant, &name, options,
+        )
+        .await;
     }
 
-    let use_existing = Confirm::new("Would you like to use an existing application?")
-        .with_default(true)
-        .with_help_message("We found existing applications that you can use. If you choose not to use them, a new application will be created.")
-        .prompt()?;
+    let use_existing = options.yes
+        || prompter.confirm(
+            &Question {
+                key: "use_existing_application",
+                message: "Would you like to use an existing application?",
+                help: Some("We found existing applications that you can use. If you choose not to use them, a new application will be created."),
+                option: None,
+            },
+            true,
+        )?;
 
     if use_existing {
-        let selected_application: Result<ApplicationResponse, InquireError> =
-            Select::new("Please select an application", applications).prompt();
+        let choices: Vec<Choice> = applications
+            .iter()
+            .map(|application| Choice {
+                id: application.id.clone(),
+                name: application.name.clone(),
+                label: application.to_string(),
+            })
+            .collect();
 
-        match selected_application {
-            Ok(application) => return Ok(application),
-            Err(error) => Err(error.into()),
-        }
+        let index = prompter.select(
+            &Question {
+                key: "application",
+                message: "Please select an application",
+                help: None,
+                option: Some("--application-id"),
+            },
+            &choices,
+        )?;
+
+        let application = applications.swap_remove(index);
+        warn_unused_bundle_id(options, &application);
+
+        Ok(application)
+    } else {
+        let name = prompt_application_name(prompter)?;
+
+        create_new_application(session, prompter, plan, tenant, &name, options)
+            .await
+    }
+}
+
+/// An existing application keeps its bundle ID, so a `--bundle-id` for it
+/// would otherwise be dropped without notice.
+fn warn_unused_bundle_id(
+    options: &BootstrapOptions,
+    application: &ApplicationResponse,
+) {
+    if let Some(bundle_id) = &options.bundle_id {
+        eprintln!(
+            "Warning: Ignoring --bundle-id {}, since the existing application \"{}\" is used.",
+            bundle_id.trim(),
+            application.name
+        );
+    }
+}
+
+/// The name of the project generated for an application.
+fn project_name(app_name: &str) -> String {
+    // If the app name ends with "App", remove it.
+    if app_name.to_lowercase().ends_with("app") {
+        app_name.trim_end_matches("app").trim().to_string()
     } else {
-        return create_new_application(&tenant).await;
+        app_name.to_string()
+    }
+}
+
+/// The directory the project is generated in, which is named after the
+/// project unless the path already ends with its name.
+fn project_dir(
+    relative_path: &str,
+    kebab_name: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let mut project_path = PathBuf::from_str(relative_path)?;
+    if !project_path.ends_with(kebab_name) {
+        project_path.push(kebab_name);
     }
+
+    Ok(expand_tilde(&project_path).unwrap())
 }
 
 fn get_project_path(
+    prompter: &mut dyn Prompter,
     project_path_arg: Option<String>,
     app_name: &str,
-) -> String {
+) -> Result<String, Box<dyn Error>> {
     if let Some(project_path) = project_path_arg {
-        return project_path;
+        return Ok(project_path);
     }
 
     let default_path = format!("./{}", app_name);
     let default_message = format!("For example: ~/Desktop/{}", app_name);
 
-    let project_path =
-        Text::new("Where would you like to create your project?")
-            .with_default(&default_path)
-            .with_validator(MinLengthValidator::new(1))
-            .with_help_message(&default_message)
-            .prompt()
-            .unwrap();
+    prompter.text(
+        &Question {
+            key: "project_path",
+            message: "Where would you like to create your project?",
+            help: Some(&default_message),
+            option: Some("--project-path"),
+        },
+        Some(&default_path),
+        validation::validate_present,
+    )
+}
 
-    return project_path;
+async fn create_new_tenant(
+    session: &impl WizardApi,
+    prompter: &mut dyn Prompter,
+    plan: &mut Plan,
+) -> Result<TenantResponse, Box<dyn Error>> {
+    let name = prompter.text(
+        &Question {
+            key: "workspace_name",
+            message: "No existing workspaces found. What would you like to call your workspace?",
+            help: None,
+            option: Some("--workspace-name"),
+        },
+        None,
+        validation::validate_present,
+    )?;
+
+    return create_tenant(session, plan, &name).await;
 }
 
-async fn create_new_tenant() -> Result<TenantResponse, Box<dyn Error>> {
-    let name = Text::new(
-        "No existing workspaces found. What would you like to call your workspace?",
-    )
-    .with_validator(MinLengthValidator::new(1))
-    .prompt()?;
+async fn create_tenant(
+    session: &impl WizardApi,
+    plan: &mut Plan,
+    name: &str,
+) -> Result<TenantResponse, Box<dyn Error>> {
+    plan.write(
+        format!("/users/{}/tenants", session.user_id()),
+        format!("Create the workspace \"{}\"", name),
+    );
 
-    return api::create_tenant(&name.trim()).await;
+    if plan.dry_run {
+        return Ok(TenantResponse {
+            id: NOT_CREATED_ID.to_string(),
+            name: name.to_string(),
+            is_test: false,
+            subdomain: None,
+            logo: None,
+            domains: Vec::new(),
+        });
+    }
+
+    session.create_tenant(name).await
+}
+
+fn prompt_application_name(
+    prompter: &mut dyn Prompter,
+) -> Result<String, Box<dyn Error>> {
+    prompter.text(
+        &Question {
+            key: "app_name",
+            message: "What would you like to call your application?",
+            help: None,
+            option: Some("--app-name"),
+        },
+        None,
+        validation::validate_present,
+    )
 }
 
 async fn create_new_application(
+    session: &impl WizardApi,
+    prompter: &mut dyn Prompter,
+    plan: &mut Plan,
     tenant: &TenantResponse,
+    name: &str,
+    options: &BootstrapOptions,
 ) -> Result<ApplicationResponse, Box<dyn Error>> {
-    let name = Text::new("What would you like to call your application?")
-        .with_validator(MinLengthValidator::new(1))
-        .prompt()?;
-
     let tenant_slug = slugify!(&tenant.name);
-    let app_name_slug = slugify!(&name);
+    let app_name_slug = slugify!(name);
 
     let suggested_bundle_id = format!("com.{}.{}", tenant_slug, app_name_slug);
 
-    let bundle_id = Text::new("What would you like your bundle ID to be?")
-        .with_default(&suggested_bundle_id)
-        .with_validator(MinLengthValidator::new(5)) // min for x.y.z
-        .with_validator(MaxLengthValidator::new(155))
-        .with_validator(|input: &str| {
-            let re =
-                Regex::new(r"^[a-zA-Z0-9-]+(\.[a-zA-Z0-9-]+){2,}$").unwrap();
+    let bundle_id = match &options.bundle_id {
+        Some(bundle_id) => bundle_id.clone(),
+        None => prompter.text(
+            &Question {
+                key: "bundle_id",
+                message: "What would you like your bundle ID to be?",
+                help: None,
+                option: Some("--bundle-id"),
+            },
+            Some(&suggested_bundle_id),
+            validation::validate_bundle_id,
+        )?,
+    };
 
-            if re.is_match(input) {
-                Ok(Validation::Valid)
-            } else {
-                Ok(Validation::Invalid("The bundle ID string must contain only alphanumeric characters (A–Z, a–z, and 0–9), hyphens (-), and periods (.). Typically, you use a reverse-DNS format for bundle ID strings. Bundle IDs are case-insensitive.".into()))
-            }
-        })
-        .prompt()?;
+    let name = name.trim();
+    let bundle_id = bundle_id.trim();
 
-    let new_application =
-        api::create_application(&tenant.id, &name.trim(), &bundle_id.trim())
-            .await?;
+    plan.write(
+        format!("/tenants/{}/applications", tenant.id),
+        format!("Create the application \"{}\" ({})", name, bundle_id),
+    );
+
+    if plan.dry_run {
+        return Ok(ApplicationResponse {
+            id: NOT_CREATED_ID.to_string(),
+            name: name.to_string(),
+            description: None,
+            r#type: ApplicationType::Ios,
+            tenant_id: tenant.id.clone(),
+            ios: Some(ApplicationIosConfig {
+                bundle_id: bundle_id.to_string(),
+            }),
+        });
+    }
+
+    let new_application = session
+        .create_application(&tenant.id, name, bundle_id)
+        .await?;
 
     return Ok(new_application);
 }
 
-fn open_project(path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    println!("🚀 Launching project! 🚀 ");
-
+fn open_project_command(path: &Path) -> Command {
     let full_path = path.to_str().unwrap().to_owned() + ".xcodeproj";
 
-    Command::new("open")
-        .arg(full_path)
-        .current_dir(path)
-        .output()?;
+    let mut command = Command::new("open");
+    command.arg(full_path).current_dir(path);
+
+    command
+}
+
+fn open_project(path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    eprintln!("🚀 Launching project! 🚀 ");
+
+    open_project_command(path).output()?;
 
     Ok(())
 }
@@ -299,3 +1090,275 @@ fn expand_tilde<P: AsRef<Path>>(path_user_input: P) -> Option<PathBuf> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn tenant(id: &str, name: &str) -> TenantResponse {
+        TenantResponse {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_test: false,
+            subdomain: None,
+            logo: None,
+            domains: Vec::new(),
+        }
+    }
+
+    fn application(id: &str, name: &str) -> ApplicationResponse {
+        ApplicationResponse {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            r#type: ApplicationType::Ios,
+            tenant_id: "tenant-1".to_string(),
+            ios: Some(ApplicationIosConfig {
+                bundle_id: "com.acme.app".to_string(),
+            }),
+        }
+    }
+
+    fn project() -> generator::RenderedProject {
+        generator::render_project(
+            tenant("tenant-1", "Acme"),
+            application("app-1", "Acme"),
+        )
+        .unwrap()
+    }
+
+    /// Workspaces and applications that already exist, instead of the API's.
+    struct FakeApi;
+
+    impl WizardApi for FakeApi {
+        fn user_id(&self) -> String {
+            "user-1".to_string()
+        }
+
+        async fn get_tenant(
+            &self,
+            tenant_id: &str,
+        ) -> Result<TenantResponse, Box<dyn Error>> {
+            Ok(tenant(tenant_id, "Acme"))
+        }
+
+        async fn list_tenants(
+            &self,
+        ) -> Result<Vec<TenantResponse>, Box<dyn Error>> {
+            Ok(vec![
+                tenant("tenant-1", "Acme"),
+                tenant("tenant-2", "Globex"),
+            ])
+        }
+
+        async fn create_tenant(
+            &self,
+            _name: &str,
+        ) -> Result<TenantResponse, Box<dyn Error>> {
+            Err("Workspaces aren't created in tests.".into())
+        }
+
+        async fn get_application(
+            &self,
+            _tenant_id: &str,
+            application_id: &str,
+        ) -> Result<ApplicationResponse, Box<dyn Error>> {
+            Ok(application(application_id, "Acme"))
+        }
+
+        async fn list_applications(
+            &self,
+            _tenant_id: &str,
+        ) -> Result<Vec<ApplicationResponse>, Box<dyn Error>> {
+            Ok(vec![application("app-1", "Acme")])
+        }
+
+        async fn create_application(
+            &self,
+            _tenant_id: &str,
+            _name: &str,
+            _bundle_id: &str,
+        ) -> Result<ApplicationResponse, Box<dyn Error>> {
+            Err("Applications aren't created in tests.".into())
+        }
+    }
+
+    fn options() -> BootstrapOptions {
+        BootstrapOptions {
+            application_id: None,
+            workspace_id: None,
+            project_path: None,
+            app_name: None,
+            bundle_id: None,
+            workspace_name: None,
+            answers: None,
+            overwrite: false,
+            // Keeps the checks from looking for Xcode.
+            install_deps: true,
+            yes: false,
+            non_interactive: true,
+            dry_run: true,
+            scratch_dir: None,
+        }
+    }
+
+    /// Loads the answers from a YAML file, with the choices they imply.
+    fn answers(name: &str, contents: &str) -> Answers {
+        let path = env::temp_dir().join(format!(
+            "parra-bootstrap-answers-{}-{}.yml",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, contents).unwrap();
+
+        let mut answers = Answers::load(&path, &ANSWER_KEYS).unwrap();
+        fs::remove_file(path).unwrap();
+        imply_choices(&mut answers);
+
+        answers
+    }
+
+    fn plan() -> Plan {
+        Plan {
+            dry_run: true,
+            api_calls: Vec::new(),
+        }
+    }
+
+    async fn choose(
+        answers: Answers,
+    ) -> Result<(TenantResponse, ApplicationResponse), Box<dyn Error>> {
+        let options = options();
+        let mut prompter =
+            AnswersPrompter::new(answers, Box::new(NonInteractivePrompter));
+        let mut plan = plan();
+
+        let tenant =
+            get_tenant(&FakeApi, &mut prompter, &mut plan, &options).await?;
+        let application = get_application(
+            &FakeApi,
+            &mut prompter,
+            &mut plan,
+            &options,
+            &tenant,
+        )
+        .await?;
+
+        Ok((tenant, application))
+    }
+
+    #[tokio::test]
+    async fn follows_an_explicit_choice() {
+        let answers = answers(
+            "explicit",
+            "use_existing_workspace: true\nworkspace: Globex\nuse_existing_application: false\napp_name: Rocket\n",
+        );
+
+        check_non_interactive_inputs(&options(), Some(&answers)).unwrap();
+
+        let (tenant, application) = choose(answers).await.unwrap();
+        assert_eq!(tenant.id, "tenant-2");
+        assert_eq!(application.id, NOT_CREATED_ID);
+        assert_eq!(application.name, "Rocket");
+    }
+
+    #[tokio::test]
+    async fn infers_the_choice_from_the_answer() {
+        let answers =
+            answers("implied", "workspace: tenant-2\napplication: Acme\n");
+
+        assert_eq!(answers.flag("use_existing_workspace"), Some(true));
+        assert_eq!(answers.flag("use_existing_application"), Some(true));
+        check_non_interactive_inputs(&options(), Some(&answers)).unwrap();
+
+        let (tenant, application) = choose(answers).await.unwrap();
+        assert_eq!(tenant.name, "Globex");
+        assert_eq!(application.id, "app-1");
+    }
+
+    #[test]
+    fn explicit_answers_take_precedence_over_implied_ones() {
+        let answers = answers(
+            "contradictory",
+            "use_existing_workspace: false\nworkspace: Globex\napp_name: Rocket\n",
+        );
+
+        assert_eq!(answers.flag("use_existing_workspace"), Some(false));
+        assert_eq!(answers.flag("use_existing_application"), Some(false));
+
+        let error = check_non_interactive_inputs(&options(), Some(&answers))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("--workspace-id or --workspace-name"));
+        assert!(!error.contains("--application-id or --app-name"));
+    }
+
+    #[tokio::test]
+    async fn reports_missing_answers() {
+        let answers = answers("missing", "install_deps: true\n");
+
+        let error = check_non_interactive_inputs(&options(), Some(&answers))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("--workspace-id or --workspace-name"));
+        assert!(error.contains("--application-id or --app-name"));
+
+        let error = choose(answers).await.unwrap_err().to_string();
+        assert!(error.contains("`use_existing_workspace`"));
+    }
+
+    #[test]
+    fn dry_run_plan_lists_missing_xcode() {
+        let plan = Plan {
+            dry_run: true,
+            api_calls: Vec::new(),
+        };
+
+        let result = dry_run_result(
+            plan,
+            Path::new("/tmp/acme"),
+            false,
+            &project(),
+            &[DerivedDependency::Xcode],
+            InstallAction::NeedsInstallDeps,
+        );
+
+        assert_eq!(result.missing_dependencies.len(), 1);
+        assert_eq!(
+            result.missing_dependencies[0].name,
+            format!("Xcode {} or later", MIN_XCODE_VERSION)
+        );
+        assert_eq!(
+            result.missing_dependencies[0].action,
+            InstallAction::NeedsInstallDeps
+        );
+        assert!(result
+            .commands
+            .iter()
+            .any(|command| command.contains("xcodes install")));
+    }
+
+    #[test]
+    fn dry_run_plan_skips_declined_installs() {
+        let plan = Plan {
+            dry_run: true,
+            api_calls: Vec::new(),
+        };
+
+        let result = dry_run_result(
+            plan,
+            Path::new("/tmp/acme"),
+            false,
+            &project(),
+            &[DerivedDependency::Xcode],
+            InstallAction::Skip,
+        );
+
+        assert!(!result
+            .commands
+            .iter()
+            .any(|command| command.contains("xcodes install")));
+    }
+}
@@ -0,0 +1,18 @@
+use crate::mock_server;
+use std::error::Error;
+use std::path::Path;
+
+pub async fn execute_mock_server(
+    port: u16,
+    fixtures: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    // The server blocks while waiting for requests, so it runs on a thread
+    // where that won't hold up the async runtime.
+    let result = tokio::task::spawn_blocking(move || {
+        mock_server::serve(port, fixtures.as_deref().map(Path::new))
+            .map_err(|error| error.to_string())
+    })
+    .await?;
+
+    result.map_err(|error| error.into())
+}
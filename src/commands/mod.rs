@@ -1 +1,5 @@
+pub mod apps;
+pub mod auth;
 pub mod bootstrap;
+pub mod dev;
+pub mod workspaces;
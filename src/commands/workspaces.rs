@@ -0,0 +1,86 @@
+use crate::api::{self, ApiSession};
+use crate::output::{self, Tabular};
+use crate::types::api::{TenantDomainType, TenantResponse};
+use std::error::Error;
+
+impl Tabular for TenantResponse {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Test", "Subdomain", "Logo", "Domains"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let mut domains: Vec<_> = self.domains.iter().collect();
+        domains.sort_by_key(|domain| domain.domain_type);
+
+        let domains: Vec<String> = domains
+            .iter()
+            .map(|domain| {
+                format!(
+                    "{} ({})",
+                    domain.url,
+                    describe_domain_type(domain.domain_type)
+                )
+            })
+            .collect();
+
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            if self.is_test { "yes" } else { "no" }.to_string(),
+            output::or_dash(self.subdomain.as_deref()),
+            output::or_dash(self.logo.as_ref().map(|logo| logo.url.as_str())),
+            if domains.is_empty() {
+                "-".to_string()
+            } else {
+                domains.join(", ")
+            },
+        ]
+    }
+}
+
+pub async fn execute_list() -> Result<(), Box<dyn Error>> {
+    let session = ApiSession::new().await?;
+    let tenants = api::paginate_tenants(&session).collect_all().await?;
+
+    if tenants.is_empty() && !output::is_structured() {
+        eprintln!("You don't have any workspaces. Run `parra workspaces create <name>` to create one.");
+
+        return Ok(());
+    }
+
+    output::print_list(&tenants)
+}
+
+pub async fn execute_show(workspace_id: &str) -> Result<(), Box<dyn Error>> {
+    let session = ApiSession::new().await?;
+    let tenant = api::get_tenant(&session, workspace_id).await?;
+
+    output::print_item(&tenant)
+}
+
+pub async fn execute_create(
+    name: &str,
+    is_test: bool,
+) -> Result<(), Box<dyn Error>> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("The workspace name can't be empty.".into());
+    }
+
+    let session = ApiSession::new().await?;
+    let tenant = api::create_tenant(&session, name, is_test).await?;
+
+    eprintln!("Created workspace {}.", tenant.name);
+
+    output::print_item(&tenant)
+}
+
+fn describe_domain_type(domain_type: TenantDomainType) -> &'static str {
+    match domain_type {
+        TenantDomainType::Managed => "managed",
+        TenantDomainType::External => "external",
+        TenantDomainType::Subdomain => "subdomain",
+        TenantDomainType::Fallback => "fallback",
+    }
+}
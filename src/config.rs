@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const DEFAULT_PROFILE_NAME: &str = "production";
+const DEFAULT_CLIENT_ID: &str = "nD9GTUvvqCT0oWi34L2IdJiK0YjupSjY";
+
+/// The port on localhost that the built-in `local` profile points at, which is
+/// also where `parra dev mock-server` listens by default.
+pub const LOCAL_PORT: u16 = 8080;
+
+static ACTIVE_PROFILE: OnceLock<Profile> = OnceLock::new();
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialStoreKind {
+    #[serde(rename = "keychain")]
+    Keychain,
+    #[serde(rename = "file")]
+    File,
+}
+
+/// User configuration for the CLI. This is read from `config.json` in the
+/// Parra config directory. Every field is optional, and a missing file is
+/// treated the same as an empty one.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Config {
+    pub credential_store: Option<CredentialStoreKind>,
+    /// The profile to use when none is provided with `--profile` or
+    /// `PARRA_PROFILE`. Defaults to `production`.
+    pub default_profile: Option<String>,
+    /// Additional profiles, or overrides for the built-in ones. Any field that
+    /// is omitted falls back to the built-in profile with the same name, or to
+    /// `production` for new profiles.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+/// Network settings for requests to the Parra API and authorization server.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct HttpConfig {
+    pub connect_timeout_secs: Option<u64>,
+    pub timeout_secs: Option<u64>,
+    /// How many times a failed request may be retried.
+    pub max_retries: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ProfileConfig {
+    pub api_url: Option<String>,
+    pub auth_url: Option<String>,
+    pub client_id: Option<String>,
+}
+
+/// A named environment that the CLI talks to. Credentials are stored
+/// separately for each profile.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    /// The base URL of the Parra API, including the version. For example:
+    /// `https://api.parra.io/v1`
+    pub api_url: String,
+    /// The base URL of the authorization server. For example:
+    /// `https://auth.parra.io`
+    pub auth_url: String,
+    pub client_id: String,
+    /// Whether `PARRA_API_URL` or `PARRA_AUTH_URL` replaced the URLs the
+    /// profile is configured with.
+    pub urls_overridden: bool,
+}
+
+impl Profile {
+    /// The name that credentials and accounts for this profile are stored
+    /// under. When the URLs are overridden, they are included so that logins
+    /// to another server never replace the ones for the profile itself.
+    pub fn storage_name(&self) -> String {
+        if self.urls_overridden {
+            format!("{}@{}|{}", self.name, self.api_url, self.auth_url)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// The key the credential for this profile is stored under. Production
+    /// keeps using the bare client ID so that existing logins are preserved.
+    pub fn credential_key(&self) -> String {
+        if self.name == DEFAULT_PROFILE_NAME && !self.urls_overridden {
+            self.client_id.clone()
+        } else {
+            format!("{}:{}", self.storage_name(), self.client_id)
+        }
+    }
+}
+
+/// The directory where the CLI stores its configuration and, when the file
+/// credential store is used, its credentials. This follows the platform
+/// convention, which is `$XDG_CONFIG_HOME/parra` on Linux.
+pub fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let base = dirs::config_dir()
+        .ok_or("Unable to determine the configuration directory")?;
+
+    Ok(base.join("parra"))
+}
+
+pub fn load_config() -> Result<Config, Box<dyn Error>> {
+    let path = config_dir()?.join("config.json");
+
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let data = fs::read_to_string(&path)?;
+
+    serde_json::from_str::<Config>(&data).map_err(|error| {
+        format!("Invalid config file at {}: {}", path.display(), error).into()
+    })
+}
+
+/// Resolves the profile to use for this invocation and makes it available
+/// through `active_profile`. The profile name is taken from the `--profile`
+/// flag, then `PARRA_PROFILE`, then `default_profile` in the config file.
+/// `PARRA_API_URL` and `PARRA_AUTH_URL` override the URLs of whichever profile
+/// is selected.
+pub fn init_active_profile(
+    profile_arg: Option<String>,
+) -> Result<&'static Profile, Box<dyn Error>> {
+    let profile = resolve_profile(profile_arg)?;
+
+    Ok(ACTIVE_PROFILE.get_or_init(|| profile))
+}
+
+pub fn active_profile() -> &'static Profile {
+    ACTIVE_PROFILE.get_or_init(|| {
+        resolve_profile(None).expect("Failed to resolve the active profile")
+    })
+}
+
+fn resolve_profile(
+    profile_arg: Option<String>,
+) -> Result<Profile, Box<dyn Error>> {
+    let config = load_config()?;
+
+    let name = profile_arg
+        .or(env::var("PARRA_PROFILE").ok())
+        .or(config.default_profile.clone())
+        .unwrap_or(DEFAULT_PROFILE_NAME.to_string());
+
+    let builtin = builtin_profile(&name);
+    let configured = config.profiles.get(&name).cloned();
+
+    if builtin.is_none() && configured.is_none() {
+        return Err(format!(
+            "Unknown profile \"{}\". Built-in profiles are production, staging and local. Others can be added to config.json.",
+            name
+        )
+        .into());
+    }
+
+    let base = builtin
+        .unwrap_or_else(|| builtin_profile(DEFAULT_PROFILE_NAME).unwrap());
+    let configured = configured.unwrap_or_default();
+    let api_url_override = env::var("PARRA_API_URL").ok();
+    let auth_url_override = env::var("PARRA_AUTH_URL").ok();
+
+    Ok(Profile {
+        name,
+        urls_overridden: api_url_override.is_some()
+            || auth_url_override.is_some(),
+        api_url: api_url_override
+            .or(configured.api_url)
+            .unwrap_or(base.api_url)
+            .trim_end_matches('/')
+            .to_string(),
+        auth_url: auth_url_override
+            .or(configured.auth_url)
+            .unwrap_or(base.auth_url)
+            .trim_end_matches('/')
+            .to_string(),
+        client_id: configured.client_id.unwrap_or(base.client_id),
+    })
+}
+
+fn builtin_profile(name: &str) -> Option<Profile> {
+    let (api_url, auth_url) = match name {
+        "production" => (
+            "https://api.parra.io/v1".to_string(),
+            "https://auth.parra.io".to_string(),
+        ),
+        "staging" => (
+            "https://api.staging.parra.io/v1".to_string(),
+            "https://auth.staging.parra.io".to_string(),
+        ),
+        "local" => (
+            format!("http://localhost:{}/v1", LOCAL_PORT),
+            format!("http://localhost:{}", LOCAL_PORT),
+        ),
+        _ => return None,
+    };
+
+    Some(Profile {
+        name: name.to_string(),
+        api_url,
+        auth_url,
+        client_id: DEFAULT_CLIENT_ID.to_string(),
+        urls_overridden: false,
+    })
+}
@@ -0,0 +1,244 @@
+use super::CredentialStore;
+use crate::config;
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+const PASSPHRASE_ENV_VAR: &str = "PARRA_CREDENTIAL_PASSPHRASE";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "format")]
+enum StoredEntry {
+    #[serde(rename = "plain")]
+    Plain { data: String },
+    #[serde(rename = "encrypted")]
+    Encrypted {
+        salt: String,
+        nonce: String,
+        ciphertext: String,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct CredentialsFile {
+    entries: BTreeMap<String, StoredEntry>,
+}
+
+/// Stores credentials in `credentials.json` in the Parra config directory.
+/// The file is only readable by the current user. If the
+/// `PARRA_CREDENTIAL_PASSPHRASE` environment variable is set, entries are
+/// encrypted with a key derived from it.
+pub struct FileStore {
+    path: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl FileStore {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let path = config::config_dir()?.join("credentials.json");
+        let passphrase = env::var(PASSPHRASE_ENV_VAR)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty());
+
+        Ok(FileStore { path, passphrase })
+    }
+
+    fn load(&self) -> Result<CredentialsFile, Box<dyn Error>> {
+        if !self.path.exists() {
+            return Ok(CredentialsFile::default());
+        }
+
+        let data = fs::read_to_string(&self.path)?;
+
+        Ok(serde_json::from_str::<CredentialsFile>(&data)?)
+    }
+
+    fn save(&self, file: &CredentialsFile) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = serde_json::to_string_pretty(file)?;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+            options.mode(0o600);
+
+            // The mode above is only applied when the file is created, so
+            // make sure an existing file is locked down too.
+            if self.path.exists() {
+                fs::set_permissions(
+                    &self.path,
+                    fs::Permissions::from_mode(0o600),
+                )?;
+            }
+        }
+
+        let mut handle = options.open(&self.path)?;
+        handle.write_all(serialized.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<StoredEntry, Box<dyn Error>> {
+        let passphrase = match &self.passphrase {
+            Some(passphrase) => passphrase,
+            None => {
+                return Ok(StoredEntry::Plain {
+                    data: String::from_utf8(data.to_vec())?,
+                })
+            }
+        };
+
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let cipher = cipher_for_passphrase(passphrase, &salt)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| "Failed to encrypt credential")?;
+
+        Ok(StoredEntry::Encrypted {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn decode(&self, entry: &StoredEntry) -> Result<Vec<u8>, Box<dyn Error>> {
+        match entry {
+            StoredEntry::Plain { data } => Ok(data.as_bytes().to_vec()),
+            StoredEntry::Encrypted {
+                salt,
+                nonce,
+                ciphertext,
+            } => {
+                let passphrase = self.passphrase.as_ref().ok_or(format!(
+                    "The stored credential is encrypted. Set {} to decrypt it.",
+                    PASSPHRASE_ENV_VAR
+                ))?;
+
+                let salt = STANDARD.decode(salt)?;
+                let nonce = STANDARD.decode(nonce)?;
+                let ciphertext = STANDARD.decode(ciphertext)?;
+
+                let cipher = cipher_for_passphrase(passphrase, &salt)?;
+
+                Ok(cipher
+                    .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                    .map_err(|_| {
+                        "Failed to decrypt the stored credential. Check the passphrase."
+                    })?)
+            }
+        }
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn read(&self, key: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let file = self.load()?;
+        let entry = file
+            .entries
+            .get(key)
+            .ok_or("No credential found in the credentials file")?;
+
+        self.decode(entry)
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut file = self.load()?;
+        file.entries.insert(key.to_string(), self.encode(data)?);
+
+        self.save(&file)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = self.load()?;
+
+        if file.entries.remove(key).is_none() {
+            return Ok(());
+        }
+
+        self.save(&file)
+    }
+}
+
+fn cipher_for_passphrase(
+    passphrase: &str,
+    salt: &[u8],
+) -> Result<ChaCha20Poly1305, Box<dyn Error>> {
+    let mut key = [0u8; 32];
+
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|error| format!("Failed to derive key: {}", error))?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CredentialStore, FileStore, StoredEntry};
+    use tempfile::TempDir;
+
+    fn store(dir: &TempDir, passphrase: Option<&str>) -> FileStore {
+        FileStore {
+            path: dir.path().join("credentials.json"),
+            passphrase: passphrase.map(|passphrase| passphrase.to_string()),
+        }
+    }
+
+    #[test]
+    fn encrypted_credentials_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir, Some("correct horse"));
+
+        store.write("key", b"{\"token\":\"secret\"}").unwrap();
+
+        let file = store.load().unwrap();
+        assert!(matches!(file.entries["key"], StoredEntry::Encrypted { .. }));
+        assert_eq!(store.read("key").unwrap(), b"{\"token\":\"secret\"}");
+    }
+
+    #[test]
+    fn rejects_the_wrong_passphrase() {
+        let dir = TempDir::new().unwrap();
+        store(&dir, Some("correct horse"))
+            .write("key", b"secret")
+            .unwrap();
+
+        let error =
+            store(&dir, Some("battery staple")).read("key").unwrap_err();
+        assert!(error.to_string().contains("Check the passphrase"));
+
+        let error = store(&dir, None).read("key").unwrap_err();
+        assert!(error.to_string().contains("is encrypted"));
+    }
+
+    #[test]
+    fn deleting_a_missing_key_succeeds() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir, None);
+
+        store.delete("missing").unwrap();
+        store.write("key", b"secret").unwrap();
+        store.delete("key").unwrap();
+
+        assert!(store.read("key").is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use super::CredentialStore;
+use security_framework::passwords;
+use std::error::Error;
+
+const SERVICE_NAME: &str = "parra_cli";
+/// The status returned when there is no Keychain item for the key.
+const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+/// Stores credentials as generic passwords in the macOS Keychain.
+pub struct KeychainStore;
+
+impl CredentialStore for KeychainStore {
+    fn read(&self, key: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(passwords::get_generic_password(SERVICE_NAME, key)?)
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        Ok(passwords::set_generic_password(SERVICE_NAME, key, data)?)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Box<dyn Error>> {
+        match passwords::delete_generic_password(SERVICE_NAME, key) {
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::cassette;
+use crate::config::{self, CredentialStoreKind};
+use std::env;
+use std::error::Error;
+
+pub mod file;
+#[cfg(target_os = "macos")]
+pub mod keychain;
+
+/// A place to persist serialized credentials. Implementations only deal with
+/// raw bytes so that every backend shares the same `Credental` format.
+pub trait CredentialStore {
+    fn read(&self, key: &str) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    fn write(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn Error>>;
+
+    /// Removes the data stored under the key. Deleting a key that has nothing
+    /// stored under it succeeds, so that an account can always be forgotten.
+    fn delete(&self, key: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Reads from another store without ever changing it. This is used while a
+/// cassette is replayed, because the tokens in replayed responses are redacted
+/// and would otherwise replace the real ones.
+pub struct ReadOnlyStore(pub Box<dyn CredentialStore>);
+
+impl CredentialStore for ReadOnlyStore {
+    fn read(&self, key: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.0.read(key)
+    }
+
+    fn write(&self, _key: &str, _data: &[u8]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn delete(&self, _key: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Returns the credential store selected by the `PARRA_CREDENTIAL_STORE`
+/// environment variable, falling back to the config file and then to the
+/// platform default. The Keychain is the default on macOS, and the file store
+/// everywhere else.
+pub fn default_store() -> Result<Box<dyn CredentialStore>, Box<dyn Error>> {
+    let kind = match env::var("PARRA_CREDENTIAL_STORE") {
+        Ok(value) => parse_kind(&value)?,
+        Err(_) => config::load_config()?
+            .credential_store
+            .unwrap_or(platform_default_kind()),
+    };
+
+    let store = store_for_kind(kind)?;
+
+    if cassette::is_replaying() {
+        return Ok(Box::new(ReadOnlyStore(store)));
+    }
+
+    Ok(store)
+}
+
+fn parse_kind(value: &str) -> Result<CredentialStoreKind, Box<dyn Error>> {
+    match value.trim().to_lowercase().as_str() {
+        "keychain" => Ok(CredentialStoreKind::Keychain),
+        "file" => Ok(CredentialStoreKind::File),
+        other => Err(format!(
+            "Unknown credential store \"{}\". Expected \"keychain\" or \"file\".",
+            other
+        )
+        .into()),
+    }
+}
+
+fn platform_default_kind() -> CredentialStoreKind {
+    if cfg!(target_os = "macos") {
+        CredentialStoreKind::Keychain
+    } else {
+        CredentialStoreKind::File
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn store_for_kind(
+    kind: CredentialStoreKind,
+) -> Result<Box<dyn CredentialStore>, Box<dyn Error>> {
+    match kind {
+        CredentialStoreKind::Keychain => Ok(Box::new(keychain::KeychainStore)),
+        CredentialStoreKind::File => Ok(Box::new(file::FileStore::new()?)),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn store_for_kind(
+    kind: CredentialStoreKind,
+) -> Result<Box<dyn CredentialStore>, Box<dyn Error>> {
+    match kind {
+        CredentialStoreKind::Keychain => {
+            Err("The keychain credential store is only available on macOS. Use the file store instead.".into())
+        }
+        CredentialStoreKind::File => Ok(Box::new(file::FileStore::new()?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CredentialStore, ReadOnlyStore};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    #[derive(Default)]
+    struct MemoryStore(RefCell<HashMap<String, Vec<u8>>>);
+
+    impl CredentialStore for MemoryStore {
+        fn read(&self, key: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+            self.0.borrow().get(key).cloned().ok_or("missing".into())
+        }
+
+        fn write(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+            self.0.borrow_mut().insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), Box<dyn Error>> {
+            self.0.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read_only_store_keeps_existing_credentials() {
+        let inner = MemoryStore::default();
+        inner.write("key", b"real").unwrap();
+
+        let store = ReadOnlyStore(Box::new(inner));
+        store.write("key", b"[REDACTED]").unwrap();
+        store.delete("key").unwrap();
+
+        assert_eq!(store.read("key").unwrap(), b"real");
+    }
+}
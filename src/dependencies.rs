@@ -13,20 +13,59 @@ pub enum DerivedDependency {
 /// project dependency. Other brew dependencies like xcodes and aria2 are installed
 /// manually at this point since they are not always used.
 pub fn install_missing_dependencies(desired_xcode_version: XcodeVersion) {
-    println!("Installing missing dependencies...");
+    eprintln!("Installing missing dependencies...");
 
     install_brew_dependencies();
     install_xcode(desired_xcode_version);
 }
 
+/// The commands `install_missing_dependencies` runs, without running them.
+pub fn install_commands(desired_xcode_version: XcodeVersion) -> Vec<Command> {
+    vec![
+        brew_install_command(),
+        xcodes_install_command(desired_xcode_version),
+    ]
+}
+
+/// Formats the command as it would be typed in a shell, including any
+/// environment variables and working directory it is run with.
+pub fn describe_command(command: &Command) -> String {
+    let mut parts: Vec<String> = command
+        .get_envs()
+        .filter_map(|(key, value)| {
+            Some(format!(
+                "{}={}",
+                key.to_string_lossy(),
+                value?.to_string_lossy()
+            ))
+        })
+        .collect();
+
+    parts.push(command.get_program().to_string_lossy().to_string());
+    parts.extend(command.get_args().map(|arg| {
+        let arg = arg.to_string_lossy();
+
+        if arg.contains(char::is_whitespace) {
+            format!("\"{}\"", arg)
+        } else {
+            arg.to_string()
+        }
+    }));
+
+    match command.get_current_dir() {
+        Some(dir) => format!("{} (in {})", parts.join(" "), dir.display()),
+        None => parts.join(" "),
+    }
+}
+
 pub fn check_for_missing_dependencies(
     min_xcode_version: XcodeVersion,
 ) -> Vec<DerivedDependency> {
-    println!("Checking for missing dependencies");
+    eprintln!("Checking for missing dependencies");
 
     let mut missing_deps = Vec::<DerivedDependency>::new();
 
-    let valid_version = check_xcode_version(min_xcode_version);
+    let valid_version = check_xcode_version("xcodebuild", min_xcode_version);
     if !valid_version {
         missing_deps.push(DerivedDependency::Xcode)
     }
@@ -34,12 +73,20 @@ pub fn check_for_missing_dependencies(
     return missing_deps;
 }
 
-fn check_xcode_version(min_version: XcodeVersion) -> bool {
-    let output = Command::new("xcodebuild")
+/// Whether the Xcode reported by `xcodebuild` is at least the minimum version.
+/// Xcode is missing if `xcodebuild` can't be run at all, like on Linux.
+fn check_xcode_version(xcodebuild: &str, min_version: XcodeVersion) -> bool {
+    let output = match Command::new(xcodebuild)
         .arg("-version")
         .stderr(Stdio::null())
         .output()
-        .expect("Failed to execute xcodebuild -version");
+    {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("Xcode isn't installed: {}", error);
+            return false;
+        }
+    };
 
     if !output.status.success() {
         return false;
@@ -70,7 +117,7 @@ fn check_xcode_version(min_version: XcodeVersion) -> bool {
 
                 if let Ok(version) = Version::parse(&version_str) {
                     if min_version_req.matches(&version) {
-                        println!("Found installed Xcode version: {}", version);
+                        eprintln!("Found installed Xcode version: {}", version);
                         return true;
                     }
                 }
@@ -78,19 +125,26 @@ fn check_xcode_version(min_version: XcodeVersion) -> bool {
         }
     }
 
-    println!("No installed Xcode version meets the minimum requirement");
+    eprintln!("No installed Xcode version meets the minimum requirement");
 
     return false;
 }
 
-fn install_brew_dependencies() {
+fn brew_install_command() -> Command {
     let dependencies = vec!["xcodes", "aria2"];
 
-    let output = Command::new("brew")
+    let mut command = Command::new("brew");
+    command
         .env("HOMEBREW_NO_INSTALL_UPGRADE", "1")
         .env("HOMEBREW_NO_AUTO_UPDATE", "1")
         .arg("install")
-        .args(dependencies)
+        .args(dependencies);
+
+    command
+}
+
+fn install_brew_dependencies() {
+    let output = brew_install_command()
         .output()
         .expect("Failed to execute command");
 
@@ -100,22 +154,32 @@ fn install_brew_dependencies() {
     }
 }
 
-fn install_xcode(version: XcodeVersion) {
+fn xcodes_install_command(version: XcodeVersion) -> Command {
     let version_string =
         format!("{}.{}.{}", version.major, version.minor, version.patch);
-    let version_string_clone = version_string.clone();
-
-    println!("Installing Xcode version: {:?}", version_string);
-
-    //  and if fail retry without
 
-    let output = Command::new("xcodes")
+    let mut command = Command::new("xcodes");
+    command
         .arg("install")
         .arg(version_string)
         // Will skip prompting for password but will result in user being prompted for password to install
         // additional tools when they launch Xcode. This will likely be more streamlined.
         .arg("--no-superuser")
-        .arg("--experimental-unxip")
+        .arg("--experimental-unxip");
+
+    command
+}
+
+fn install_xcode(version: XcodeVersion) {
+    let version_string =
+        format!("{}.{}.{}", version.major, version.minor, version.patch);
+    let version_string_clone = version_string.clone();
+
+    eprintln!("Installing Xcode version: {:?}", version_string);
+
+    //  and if fail retry without
+
+    let output = xcodes_install_command(version)
         // Need to inherit stdio to allow the user to enter credentials when prompted by xcodes.
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
@@ -128,7 +192,7 @@ fn install_xcode(version: XcodeVersion) {
         eprintln!("Command failed. Error:\n{}", stderr);
     }
 
-    println!("Successfully installed Xcode: {}", version_string_clone);
+    eprintln!("Successfully installed Xcode: {}", version_string_clone);
 }
 
 fn ensure_full_semver(version: &str) -> String {
@@ -140,3 +204,22 @@ fn ensure_full_semver(version: &str) -> String {
         version.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xcode_is_missing_without_xcodebuild() {
+        let min_version = XcodeVersion {
+            major: 15,
+            minor: 3,
+            patch: 0,
+        };
+
+        assert!(!check_xcode_version(
+            "parra-test-no-xcodebuild",
+            min_version
+        ));
+    }
+}
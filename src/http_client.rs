@@ -0,0 +1,263 @@
+use crate::{cassette, config, logging};
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use semver::Version;
+use std::env;
+use std::error::Error;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Sent with every request, so that the server knows which version of the CLI
+/// made it.
+const VERSION_HEADER: &str = "x-parra-cli-version";
+/// Sent by the server with the oldest version of the CLI it still supports.
+const MIN_VERSION_HEADER: &str = "x-parra-min-cli-version";
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+static MIN_SUPPORTED_VERSION: OnceLock<Version> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy)]
+struct HttpSettings {
+    connect_timeout: Duration,
+    timeout: Duration,
+    max_retries: u32,
+}
+
+/// The client used for every request to the Parra API and the authorization
+/// server, so that connections are pooled for the lifetime of the process.
+pub fn shared_client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        let settings = settings();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            VERSION_HEADER,
+            HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
+        );
+
+        Client::builder()
+            .user_agent(user_agent())
+            .default_headers(headers)
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.timeout)
+            .build()
+            .expect("Failed to create HTTP client")
+    })
+}
+
+/// Sends the request with the shared client. When a cassette directory is set
+/// with `PARRA_HTTP_RECORD`, the request and its response are written to it,
+/// and with `PARRA_HTTP_REPLAY` the recorded response is returned without
+/// using the network.
+pub async fn send(builder: RequestBuilder) -> Result<Response, Box<dyn Error>> {
+    let request = builder.build()?;
+
+    if cassette::is_replaying() {
+        logging::log_request(&request, 0);
+
+        let start = Instant::now();
+        let response = cassette::replay(&request)?;
+        logging::log_response(&response, start.elapsed());
+        record_min_supported_version(&response);
+
+        return Ok(response);
+    }
+
+    if !cassette::is_recording() {
+        let response = send_with_retries(request).await?;
+        record_min_supported_version(&response);
+
+        return Ok(response);
+    }
+
+    let recorded_request = cassette::RecordedRequest::from_request(&request);
+    let response = send_with_retries(request).await?;
+    record_min_supported_version(&response);
+
+    cassette::record(recorded_request, response).await
+}
+
+/// Identifies the CLI version and the platform it is running on, for example
+/// `parra-cli/0.1.0 (macos; aarch64)`.
+fn user_agent() -> String {
+    format!(
+        "parra-cli/{} ({}; {})",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        env::consts::ARCH
+    )
+}
+
+fn record_min_supported_version(response: &Response) {
+    let version = response
+        .headers()
+        .get(MIN_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Version::parse(value.trim()).ok());
+
+    if let Some(version) = version {
+        let _ = MIN_SUPPORTED_VERSION.set(version);
+    }
+}
+
+/// The oldest version of the CLI the server still supports, if one has been
+/// advertised in a response during this invocation, and this version is
+/// older than it.
+pub fn unsupported_version() -> Option<&'static Version> {
+    let minimum = MIN_SUPPORTED_VERSION.get()?;
+    let current = Version::parse(env!("CARGO_PKG_VERSION")).ok()?;
+
+    (current < *minimum).then_some(minimum)
+}
+
+/// Sends the request, retrying when it is likely to
+/// succeed on another attempt. Idempotent requests are retried after
+/// connection failures and server errors. Any request is retried after a 429,
+/// since the server didn't process it, waiting for the duration in the
+/// `Retry-After` header if there is one. Other retries use exponential backoff
+/// with jitter.
+async fn send_with_retries(
+    request: reqwest::Request,
+) -> Result<Response, reqwest::Error> {
+    let client = shared_client();
+    let max_retries = settings().max_retries;
+    let idempotent = is_idempotent(request.method());
+
+    let mut attempt = 0;
+
+    loop {
+        // Requests with streaming bodies can't be cloned, so they can only be
+        // sent once. Neither can the final attempt be retried.
+        let next_request = match request.try_clone() {
+            Some(next_request) if attempt < max_retries => next_request,
+            _ => return execute(client, request, attempt).await,
+        };
+
+        let delay = match execute(client, next_request, attempt).await {
+            Ok(response) => {
+                let status = response.status();
+
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    retry_after(&response)
+                        .unwrap_or_else(|| backoff_delay(attempt))
+                } else if status.is_server_error() && idempotent {
+                    backoff_delay(attempt)
+                } else {
+                    return Ok(response);
+                }
+            }
+            Err(error) => {
+                let retryable = error.is_connect()
+                    || error.is_timeout()
+                    || error.is_request();
+
+                if !(retryable && idempotent) {
+                    return Err(error);
+                }
+
+                backoff_delay(attempt)
+            }
+        };
+
+        async_std::task::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+async fn execute(
+    client: &Client,
+    request: reqwest::Request,
+    attempt: u32,
+) -> Result<Response, reqwest::Error> {
+    logging::log_request(&request, attempt);
+
+    let start = Instant::now();
+    let result = client.execute(request).await;
+
+    match &result {
+        Ok(response) => logging::log_response(response, start.elapsed()),
+        Err(error) => logging::log_error(error, start.elapsed()),
+    }
+
+    result
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET
+            | Method::HEAD
+            | Method::OPTIONS
+            | Method::PUT
+            | Method::DELETE
+    )
+}
+
+/// Full jitter backoff: a random delay between zero and the exponentially
+/// increasing cap for this attempt.
+fn backoff_delay(attempt: u32) -> Duration {
+    let cap = BASE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY);
+
+    let millis = rand::rng().random_range(0..=cap.as_millis() as u64);
+
+    Duration::from_millis(millis)
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_DELAY))
+}
+
+/// Timeouts and retries can be set in the `http` section of the config file,
+/// and overridden with the `PARRA_HTTP_CONNECT_TIMEOUT`, `PARRA_HTTP_TIMEOUT`
+/// and `PARRA_HTTP_MAX_RETRIES` environment variables. Timeouts are in
+/// seconds.
+fn settings() -> HttpSettings {
+    *SETTINGS.get_or_init(|| {
+        let http_config = config::load_config()
+            .map(|config| config.http)
+            .unwrap_or_default();
+
+        let env_number = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        HttpSettings {
+            connect_timeout: Duration::from_secs(
+                env_number("PARRA_HTTP_CONNECT_TIMEOUT")
+                    .or(http_config.connect_timeout_secs)
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ),
+            timeout: Duration::from_secs(
+                env_number("PARRA_HTTP_TIMEOUT")
+                    .or(http_config.timeout_secs)
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            max_retries: env_number("PARRA_HTTP_MAX_RETRIES")
+                .map(|value| value as u32)
+                .or(http_config.max_retries)
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+        }
+    })
+}
@@ -0,0 +1,23 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{Map, Value};
+use std::error::Error;
+
+/// Decodes the claims in the payload of a JWT. The signature is not verified,
+/// so the claims must only be used for display and for decisions the server
+/// will check again, like whether a token has expired.
+pub fn decode_claims(
+    token: &str,
+) -> Result<Map<String, Value>, Box<dyn Error>> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or("The access token is not a JWT")?;
+
+    // Some encoders include padding even though JWTs shouldn't have it.
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('='))?;
+
+    match serde_json::from_slice::<Value>(&bytes)? {
+        Value::Object(claims) => Ok(claims),
+        _ => Err("The access token payload is not a JSON object".into()),
+    }
+}
@@ -0,0 +1,264 @@
+use reqwest::header::HeaderMap;
+use reqwest::{Request, Response};
+use serde_json::Value;
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static LOG_LEVEL: OnceLock<u8> = OnceLock::new();
+
+/// Each request and response, with its status and timing.
+pub const LEVEL_REQUESTS: u8 = 1;
+/// Request and response headers.
+pub const LEVEL_HEADERS: u8 = 2;
+/// Request and response bodies.
+pub const LEVEL_BODIES: u8 = 3;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Fields in request and response bodies whose values are never logged.
+const SECRET_FIELDS: [&str; 7] = [
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "device_code",
+    "code_verifier",
+    "client_secret",
+    "password",
+];
+
+/// Fields that are only secret in form bodies, which are only sent to the
+/// authorization server. API resources can have fields with these names too.
+const SECRET_FORM_FIELDS: [&str; 2] = ["code", "token"];
+
+const SECRET_HEADERS: [&str; 3] = ["authorization", "cookie", "set-cookie"];
+
+/// Sets the log level from the number of times `--verbose` was passed. If it
+/// wasn't, the `PARRA_LOG` environment variable is used, which accepts a
+/// level from 0 to 3, or one of `off`, `info`, `debug` and `trace`.
+pub fn init_log_level(verbose: u8) {
+    LOG_LEVEL.get_or_init(|| {
+        if verbose > 0 {
+            return verbose.min(LEVEL_BODIES);
+        }
+
+        let value = env::var("PARRA_LOG").unwrap_or_default();
+
+        match value.trim().to_lowercase().as_str() {
+            "" | "off" => 0,
+            "info" => LEVEL_REQUESTS,
+            "debug" => LEVEL_HEADERS,
+            "trace" => LEVEL_BODIES,
+            other => other.parse::<u8>().unwrap_or(0).min(LEVEL_BODIES),
+        }
+    });
+}
+
+pub fn is_enabled(level: u8) -> bool {
+    LOG_LEVEL.get().copied().unwrap_or(0) >= level
+}
+
+pub fn log_request(request: &Request, attempt: u32) {
+    if !is_enabled(LEVEL_REQUESTS) {
+        return;
+    }
+
+    if attempt > 0 {
+        eprintln!(
+            "--> {} {} (retry {})",
+            request.method(),
+            request.url(),
+            attempt
+        );
+    } else {
+        eprintln!("--> {} {}", request.method(), request.url());
+    }
+
+    log_headers(request.headers());
+
+    if is_enabled(LEVEL_BODIES) {
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            log_body(&String::from_utf8_lossy(body));
+        }
+    }
+}
+
+pub fn log_response(response: &Response, elapsed: Duration) {
+    if !is_enabled(LEVEL_REQUESTS) {
+        return;
+    }
+
+    eprintln!(
+        "<-- {} {} ({}ms)",
+        response.status(),
+        response.url(),
+        elapsed.as_millis()
+    );
+
+    log_headers(response.headers());
+}
+
+pub fn log_error(error: &reqwest::Error, elapsed: Duration) {
+    if !is_enabled(LEVEL_REQUESTS) {
+        return;
+    }
+
+    eprintln!("<-- failed after {}ms: {}", elapsed.as_millis(), error);
+}
+
+/// Logs the body of a response. Responses are read by the caller, so this is
+/// called once the body is available rather than from `log_response`.
+pub fn log_response_body(body: &str) {
+    if is_enabled(LEVEL_BODIES) {
+        log_body(body);
+    }
+}
+
+fn log_headers(headers: &HeaderMap) {
+    if !is_enabled(LEVEL_HEADERS) {
+        return;
+    }
+
+    for (name, value) in headers {
+        let value = if is_secret_header(name.as_str()) {
+            REDACTED
+        } else {
+            value.to_str().unwrap_or("[binary]")
+        };
+
+        eprintln!("    {}: {}", name, value);
+    }
+}
+
+pub fn is_secret_header(name: &str) -> bool {
+    SECRET_HEADERS.contains(&name.to_lowercase().as_str())
+}
+
+fn log_body(body: &str) {
+    if !body.is_empty() {
+        eprintln!("    {}", redact_body(body));
+    }
+}
+
+/// Replaces the values of secret fields in a JSON or form encoded body. Bodies
+/// in any other format are omitted, since they can't be redacted reliably.
+fn redact_body(body: &str) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+
+    if let Ok(mut value) = serde_json::from_str::<Value>(body) {
+        redact_json(&mut value);
+
+        return value.to_string();
+    }
+
+    let is_form = body.split('&').all(|pair| pair.contains('='))
+        && !body.contains(char::is_whitespace);
+
+    if !is_form {
+        return format!("[{} byte body omitted]", body.len());
+    }
+
+    body.split('&')
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+            if SECRET_FIELDS.contains(&key) || SECRET_FORM_FIELDS.contains(&key)
+            {
+                format!("{}={}", key, REDACTED)
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn redact(value: Value) -> Value {
+        serde_json::from_str(&redact_body(&value.to_string())).unwrap()
+    }
+
+    #[test]
+    fn redacts_nested_json_fields() {
+        let redacted = redact(json!({
+            "user": { "id": "user-1", "session": { "refresh_token": "secret" } },
+            "access_token": "secret",
+        }));
+
+        assert_eq!(
+            redacted,
+            json!({
+                "user": { "id": "user-1", "session": { "refresh_token": REDACTED } },
+                "access_token": REDACTED,
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_json_arrays() {
+        let redacted = redact(json!([
+            { "id_token": "secret", "name": "first" },
+            [{ "client_secret": "secret" }],
+        ]));
+
+        assert_eq!(
+            redacted,
+            json!([
+                { "id_token": REDACTED, "name": "first" },
+                [{ "client_secret": REDACTED }],
+            ])
+        );
+    }
+
+    #[test]
+    fn keeps_ordinary_json_fields() {
+        let body = json!({ "code": "promo", "token": "page-2" });
+
+        assert_eq!(redact(body.clone()), body);
+    }
+
+    #[test]
+    fn redacts_form_fields() {
+        assert_eq!(
+            redact_body("grant_type=authorization_code&code=abc&code_verifier=xyz"),
+            "grant_type=authorization_code&code=[REDACTED]&code_verifier=[REDACTED]"
+        );
+        assert_eq!(
+            redact_body("token=abc&token_type_hint=refresh_token"),
+            "token=[REDACTED]&token_type_hint=refresh_token"
+        );
+    }
+
+    #[test]
+    fn omits_other_bodies() {
+        assert_eq!(
+            redact_body("access_token is abc"),
+            "[19 byte body omitted]"
+        );
+
+        let binary = String::from_utf8_lossy(&[0xff, 0xfe, 0x00, 0x01]);
+        assert!(redact_body(&binary).ends_with("byte body omitted]"));
+    }
+}
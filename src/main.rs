@@ -1,31 +1,170 @@
+// Results are written through `output`, which exits quietly when stdout is
+// closed instead of panicking like `println!`.
+#![deny(clippy::print_stdout)]
+
 use std::error::Error;
+use std::process::exit;
+mod accounts;
 mod api;
+mod api_error;
 mod arg_parser;
 mod auth;
+mod cassette;
 mod commands;
+mod config;
+mod credential_store;
 mod dependencies;
+mod http_client;
+mod jwt;
+mod logging;
+mod mock_server;
+mod output;
+mod pkce;
 mod project_generator;
+mod prompt;
 mod types;
+mod validation;
 
-use crate::arg_parser::Commands;
+use crate::arg_parser::{
+    AppsCommands, AuthCommands, Commands, DevCommands, WorkspacesCommands,
+};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() {
+    if let Err(error) = run().await {
+        eprintln!("Error: {}", error);
+
+        exit(api_error::exit_code_for_error(error.as_ref()));
+    }
+}
+
+async fn run() -> Result<(), Box<dyn Error>> {
     let cli = arg_parser::parse_args();
 
+    logging::init_log_level(cli.verbose);
+    output::init_output_format(cli.output);
+    config::init_active_profile(cli.profile)?;
+    accounts::init_selected_account(cli.account);
+
     match cli.command {
         Commands::Bootstrap {
             application_id,
             workspace_id,
             project_path,
+            app_name,
+            bundle_id,
+            workspace_name,
+            answers,
+            overwrite,
+            install_deps,
+            yes,
+            non_interactive,
+            dry_run,
+            scratch_dir,
         } => {
             commands::bootstrap::execute_bootstrap(
-                application_id,
-                workspace_id,
-                project_path,
+                commands::bootstrap::BootstrapOptions {
+                    application_id,
+                    workspace_id,
+                    project_path,
+                    app_name,
+                    bundle_id,
+                    workspace_name,
+                    answers,
+                    overwrite: overwrite || yes,
+                    install_deps: install_deps || yes,
+                    yes,
+                    non_interactive,
+                    dry_run,
+                    scratch_dir,
+                },
             )
             .await?
         }
+        Commands::Login { method, no_browser } => {
+            commands::auth::execute_login(method, no_browser).await?
+        }
+        Commands::Logout => commands::auth::execute_logout().await?,
+        Commands::Whoami => commands::auth::execute_whoami().await?,
+        Commands::Auth { command } => match command {
+            AuthCommands::List => commands::auth::execute_list()?,
+            AuthCommands::Switch { user } => {
+                commands::auth::execute_switch(&user)?
+            }
+            AuthCommands::Token => commands::auth::execute_token().await?,
+            AuthCommands::Status { json } => {
+                commands::auth::execute_status(json).await?
+            }
+        },
+        Commands::Workspaces { command } => match command {
+            WorkspacesCommands::List => {
+                commands::workspaces::execute_list().await?
+            }
+            WorkspacesCommands::Show { workspace_id } => {
+                commands::workspaces::execute_show(&workspace_id).await?
+            }
+            WorkspacesCommands::Create { name, test } => {
+                commands::workspaces::execute_create(&name, test).await?
+            }
+        },
+        Commands::Apps { command } => match command {
+            AppsCommands::List { workspace } => {
+                commands::apps::execute_list(workspace).await?
+            }
+            AppsCommands::Show {
+                application_id,
+                workspace,
+            } => {
+                commands::apps::execute_show(&application_id, workspace).await?
+            }
+            AppsCommands::Create {
+                workspace,
+                name,
+                bundle_id,
+                description,
+            } => {
+                commands::apps::execute_create(
+                    workspace,
+                    &name,
+                    &bundle_id,
+                    description,
+                )
+                .await?
+            }
+            AppsCommands::Update {
+                application_id,
+                workspace,
+                name,
+                description,
+                bundle_id,
+            } => {
+                commands::apps::execute_update(
+                    &application_id,
+                    workspace,
+                    name,
+                    description,
+                    bundle_id,
+                )
+                .await?
+            }
+            AppsCommands::Delete {
+                application_id,
+                workspace,
+                confirm,
+            } => {
+                commands::apps::execute_delete(
+                    &application_id,
+                    workspace,
+                    &confirm,
+                )
+                .await?
+            }
+        },
+        Commands::Dev { command } => match command {
+            DevCommands::MockServer { port, fixtures } => {
+                commands::dev::execute_mock_server(port, fixtures).await?
+            }
+        },
     }
 
     Ok(())
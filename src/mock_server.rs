@@ -0,0 +1,667 @@
+use crate::config;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const ACCESS_TOKEN_LIFETIME: u64 = 3600;
+const DEFAULT_PAGE_SIZE: usize = 100;
+const SCOPE: &str = "openid profile email offline_access";
+
+/// The data served by the mock server. Workspaces and applications use the
+/// same JSON as the Parra API. Applications belong to the workspace in their
+/// `tenant_id`.
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    user: Value,
+    #[serde(default)]
+    tenants: Vec<Value>,
+    #[serde(default)]
+    applications: Vec<Value>,
+    #[serde(default)]
+    device_flow: DeviceFlowFixture,
+    /// Advertised as the oldest supported CLI version on every response.
+    min_cli_version: Option<String>,
+}
+
+/// How the device authorization flow behaves. Each device code is answered
+/// with `slow_down` and then `authorization_pending` for the configured number
+/// of polls before the login is approved, or denied if `deny` is set.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+struct DeviceFlowFixture {
+    interval: u64,
+    expires_in: u64,
+    slow_down_polls: u32,
+    pending_polls: u32,
+    deny: bool,
+}
+
+impl Default for DeviceFlowFixture {
+    fn default() -> Self {
+        DeviceFlowFixture {
+            interval: 1,
+            expires_in: 900,
+            slow_down_polls: 1,
+            pending_polls: 2,
+            deny: false,
+        }
+    }
+}
+
+pub enum MockResponse {
+    Json(u16, Value),
+    Empty(u16),
+    Redirect(String),
+}
+
+struct MockState {
+    fixture: Fixture,
+    base_url: String,
+    /// The number of times each outstanding device code has been polled.
+    device_codes: HashMap<String, u32>,
+    authorization_codes: HashSet<String>,
+    refresh_tokens: HashSet<String>,
+}
+
+/// Serves a mock of the Parra API and authorization server on localhost until
+/// the process is stopped. Data created through the API is kept in memory, so
+/// every run starts from the fixture.
+pub fn serve(
+    port: u16,
+    fixture_path: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let fixture = match fixture_path {
+        Some(path) => {
+            let data = fs::read_to_string(path).map_err(|error| {
+                format!("Failed to read {}: {}", path.display(), error)
+            })?;
+
+            serde_json::from_str::<Fixture>(&data).map_err(|error| {
+                format!("Invalid fixture file {}: {}", path.display(), error)
+            })?
+        }
+        None => default_fixture(),
+    };
+
+    let server = Server::http(("127.0.0.1", port)).map_err(|error| {
+        format!("Failed to start the mock server: {}", error)
+    })?;
+
+    let base_url = format!("http://127.0.0.1:{}", port);
+
+    eprintln!("Mock Parra API listening on {}", base_url);
+
+    // The URLs are only needed when they differ from the local profile's, and
+    // are paired with it so that logins to the mock are stored with the local
+    // profile rather than the production one.
+    if port == config::LOCAL_PORT {
+        eprintln!("Use it with --profile local or PARRA_PROFILE=local");
+    } else {
+        eprintln!(
+            "Use it with --profile local by setting PARRA_API_URL={}/v1 and PARRA_AUTH_URL={}",
+            base_url, base_url
+        );
+    }
+
+    let mut state = MockState {
+        fixture,
+        base_url,
+        device_codes: HashMap::new(),
+        authorization_codes: HashSet::new(),
+        refresh_tokens: HashSet::new(),
+    };
+
+    for mut request in server.incoming_requests() {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+
+        let response = state.handle(&mut request);
+
+        let min_version_header = state
+            .fixture
+            .min_cli_version
+            .as_ref()
+            .map(|version| header("X-Parra-Min-CLI-Version", version));
+
+        let result = match response {
+            MockResponse::Json(status, body) => {
+                eprintln!("{} {} {}", method, url, status);
+
+                let response = Response::from_string(body.to_string())
+                    .with_status_code(status)
+                    .with_header(header("Content-Type", "application/json"));
+
+                request.respond(with_min_version(response, min_version_header))
+            }
+            MockResponse::Empty(status) => {
+                eprintln!("{} {} {}", method, url, status);
+
+                request.respond(with_min_version(
+                    Response::empty(status),
+                    min_version_header,
+                ))
+            }
+            MockResponse::Redirect(location) => {
+                eprintln!("{} {} 302", method, url);
+
+                let response = Response::empty(302)
+                    .with_header(header("Location", &location));
+
+                request.respond(with_min_version(response, min_version_header))
+            }
+        };
+
+        if let Err(error) = result {
+            eprintln!("Failed to respond to {} {}: {}", method, url, error);
+        }
+    }
+
+    Ok(())
+}
+
+impl MockState {
+    fn handle(&mut self, request: &mut Request) -> MockResponse {
+        let url = match Url::parse(&self.base_url)
+            .and_then(|base| base.join(request.url()))
+        {
+            Ok(url) => url,
+            Err(_) => return error_response(400, "Invalid URL."),
+        };
+
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            return error_response(400, "Invalid request body.");
+        }
+
+        let is_authorized = request.headers().iter().any(|header| {
+            header.field.equiv("Authorization")
+                && header.value.as_str().starts_with("Bearer ")
+                && header.value.as_str().len() > "Bearer ".len()
+        });
+
+        let method = request.method().clone();
+        let path = url.path().trim_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').collect();
+
+        if segments.first() == Some(&"v1") && !is_authorized {
+            return error_response(401, "Missing bearer token.");
+        }
+
+        match (method, segments.as_slice()) {
+            (Method::Get, ["authorize"]) => self.authorize(&url),
+            (Method::Post, ["oauth", "device", "code"]) => self.device_code(),
+            (Method::Post, ["oauth", "token"]) => {
+                self.token(&parse_fields(&body))
+            }
+            (Method::Post, ["oauth", "revoke"]) => {
+                if let Some(token) = parse_fields(&body).get("token") {
+                    self.refresh_tokens.remove(token);
+                }
+
+                MockResponse::Json(200, json!({}))
+            }
+            (Method::Get, ["v1", "user-info"]) => {
+                MockResponse::Json(200, json!({ "user": self.fixture.user }))
+            }
+            (Method::Get, ["v1", "users", user_id, "tenants"]) => {
+                if *user_id != id_of(&self.fixture.user) {
+                    return error_response(
+                        403,
+                        "You can only list your own workspaces.",
+                    );
+                }
+
+                paginate(self.fixture.tenants.clone(), &url)
+            }
+            (Method::Post, ["v1", "users", user_id, "tenants"]) => {
+                if *user_id != id_of(&self.fixture.user) {
+                    return error_response(
+                        403,
+                        "You can only create workspaces for yourself.",
+                    );
+                }
+
+                self.create_tenant(&body)
+            }
+            (Method::Get, ["v1", "tenants", tenant_id]) => {
+                match self.find_tenant(tenant_id) {
+                    Some(tenant) => MockResponse::Json(200, tenant.clone()),
+                    None => error_response(404, "Workspace not found."),
+                }
+            }
+            (Method::Get, ["v1", "tenants", tenant_id, "applications"]) => {
+                if self.find_tenant(tenant_id).is_none() {
+                    return error_response(404, "Workspace not found.");
+                }
+
+                let applications = self
+                    .fixture
+                    .applications
+                    .iter()
+                    .filter(|application| {
+                        application["tenant_id"].as_str() == Some(*tenant_id)
+                    })
+                    .cloned()
+                    .collect();
+
+                paginate(applications, &url)
+            }
+            (Method::Post, ["v1", "tenants", tenant_id, "applications"]) => {
+                if self.find_tenant(tenant_id).is_none() {
+                    return error_response(404, "Workspace not found.");
+                }
+
+                self.create_application(tenant_id, &body)
+            }
+            (
+                Method::Get,
+                ["v1", "tenants", tenant_id, "applications", application_id],
+            ) => match self.find_application(tenant_id, application_id) {
+                Some(application) => {
+                    MockResponse::Json(200, application.clone())
+                }
+                None => error_response(404, "Application not found."),
+            },
+            (
+                Method::Put,
+                ["v1", "tenants", tenant_id, "applications", application_id],
+            ) => self.update_application(tenant_id, application_id, &body),
+            (
+                Method::Delete,
+                ["v1", "tenants", tenant_id, "applications", application_id],
+            ) => {
+                let count = self.fixture.applications.len();
+
+                self.fixture.applications.retain(|application| {
+                    id_of(application) != *application_id
+                        || application["tenant_id"].as_str() != Some(*tenant_id)
+                });
+
+                if self.fixture.applications.len() == count {
+                    return error_response(404, "Application not found.");
+                }
+
+                MockResponse::Empty(204)
+            }
+            _ => error_response(404, "Not found."),
+        }
+    }
+
+    fn find_tenant(&self, tenant_id: &str) -> Option<&Value> {
+        self.fixture
+            .tenants
+            .iter()
+            .find(|tenant| id_of(tenant) == tenant_id)
+    }
+
+    fn find_application(
+        &self,
+        tenant_id: &str,
+        application_id: &str,
+    ) -> Option<&Value> {
+        self.fixture.applications.iter().find(|application| {
+            id_of(application) == application_id
+                && application["tenant_id"].as_str() == Some(tenant_id)
+        })
+    }
+
+    fn create_tenant(&mut self, body: &str) -> MockResponse {
+        let request = serde_json::from_str::<Value>(body).unwrap_or_default();
+
+        let name = match request["name"].as_str() {
+            Some(name) if !name.trim().is_empty() => name.trim(),
+            _ => return validation_response("name", "A name is required."),
+        };
+
+        let tenant = json!({
+            "id": random_id(),
+            "name": name,
+            "is_test": request["is_test"].as_bool().unwrap_or(false),
+            "subdomain": null,
+            "logo": null,
+            "domains": [],
+        });
+
+        self.fixture.tenants.push(tenant.clone());
+
+        MockResponse::Json(200, tenant)
+    }
+
+    fn create_application(
+        &mut self,
+        tenant_id: &str,
+        body: &str,
+    ) -> MockResponse {
+        let request = serde_json::from_str::<Value>(body).unwrap_or_default();
+
+        let name = match request["name"].as_str() {
+            Some(name) if !name.trim().is_empty() => name.trim(),
+            _ => return validation_response("name", "A name is required."),
+        };
+
+        let bundle_id = match request["ios_bundle_id"].as_str() {
+            Some(bundle_id) if !bundle_id.trim().is_empty() => bundle_id.trim(),
+            _ => {
+                return validation_response(
+                    "ios_bundle_id",
+                    "A bundle ID is required.",
+                )
+            }
+        };
+
+        let application = json!({
+            "id": random_id(),
+            "name": name,
+            "description": request["description"],
+            "type": "ios",
+            "tenant_id": tenant_id,
+            "ios": { "bundle_id": bundle_id },
+        });
+
+        self.fixture.applications.push(application.clone());
+
+        MockResponse::Json(200, application)
+    }
+
+    fn update_application(
+        &mut self,
+        tenant_id: &str,
+        application_id: &str,
+        body: &str,
+    ) -> MockResponse {
+        let request = serde_json::from_str::<Value>(body).unwrap_or_default();
+
+        let application =
+            match self.fixture.applications.iter_mut().find(|application| {
+                id_of(application) == application_id
+                    && application["tenant_id"].as_str() == Some(tenant_id)
+            }) {
+                Some(application) => application,
+                None => return error_response(404, "Application not found."),
+            };
+
+        if let Some(name) = request["name"].as_str() {
+            if name.trim().is_empty() {
+                return validation_response("name", "A name is required.");
+            }
+
+            application["name"] = json!(name.trim());
+        }
+
+        if let Some(description) = request.get("description") {
+            application["description"] = description.clone();
+        }
+
+        if let Some(bundle_id) = request["ios_bundle_id"].as_str() {
+            application["ios"] = json!({ "bundle_id": bundle_id });
+        }
+
+        MockResponse::Json(200, application.clone())
+    }
+
+    /// Approves the login immediately by redirecting back to the client with
+    /// an authorization code.
+    fn authorize(&mut self, url: &Url) -> MockResponse {
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.to_string())
+        };
+
+        let mut redirect_uri = match param("redirect_uri")
+            .and_then(|redirect_uri| Url::parse(&redirect_uri).ok())
+        {
+            Some(redirect_uri) => redirect_uri,
+            None => return error_response(400, "A redirect_uri is required."),
+        };
+
+        let code = random_id();
+        self.authorization_codes.insert(code.clone());
+
+        redirect_uri.query_pairs_mut().append_pair("code", &code);
+
+        if let Some(state) = param("state") {
+            redirect_uri.query_pairs_mut().append_pair("state", &state);
+        }
+
+        MockResponse::Redirect(redirect_uri.to_string())
+    }
+
+    fn device_code(&mut self) -> MockResponse {
+        let device_code = random_id();
+        let user_code = random_id()[..8].to_uppercase();
+        let device_flow = &self.fixture.device_flow;
+
+        self.device_codes.insert(device_code.clone(), 0);
+
+        let verification_uri = format!("{}/activate", self.base_url);
+
+        MockResponse::Json(
+            200,
+            json!({
+                "device_code": device_code,
+                "user_code": user_code,
+                "verification_uri": verification_uri,
+                "verification_uri_complete": format!("{}?user_code={}", verification_uri, user_code),
+                "expires_in": device_flow.expires_in,
+                "interval": device_flow.interval,
+            }),
+        )
+    }
+
+    fn token(&mut self, fields: &HashMap<String, String>) -> MockResponse {
+        let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+
+        match field("grant_type").as_str() {
+            "urn:ietf:params:oauth:grant-type:device_code" => {
+                let device_code = field("device_code");
+                let device_flow = self.fixture.device_flow.clone();
+
+                let polls = match self.device_codes.get_mut(&device_code) {
+                    Some(polls) => {
+                        *polls += 1;
+                        *polls
+                    }
+                    None => return oauth_error(400, "expired_token"),
+                };
+
+                if polls <= device_flow.slow_down_polls {
+                    return oauth_error(400, "slow_down");
+                }
+
+                if polls
+                    <= device_flow.slow_down_polls + device_flow.pending_polls
+                {
+                    return oauth_error(400, "authorization_pending");
+                }
+
+                self.device_codes.remove(&device_code);
+
+                if device_flow.deny {
+                    return oauth_error(403, "access_denied");
+                }
+
+                self.issue_tokens(true)
+            }
+            "authorization_code" => {
+                if !self.authorization_codes.remove(&field("code")) {
+                    return oauth_error(403, "invalid_grant");
+                }
+
+                self.issue_tokens(true)
+            }
+            "refresh_token" => {
+                // Refresh tokens are rotated, so each one can only be used once.
+                if !self.refresh_tokens.remove(&field("refresh_token")) {
+                    return oauth_error(403, "invalid_grant");
+                }
+
+                self.issue_tokens(true)
+            }
+            "client_credentials" => self.issue_tokens(false),
+            _ => oauth_error(400, "unsupported_grant_type"),
+        }
+    }
+
+    fn issue_tokens(&mut self, include_refresh_token: bool) -> MockResponse {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let claims = json!({
+            "sub": id_of(&self.fixture.user),
+            "scope": SCOPE,
+            "iat": now,
+            "exp": now + ACCESS_TOKEN_LIFETIME,
+        });
+
+        let access_token = format!(
+            "{}.{}.mock",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "none" }).to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+
+        let mut response = json!({
+            "access_token": access_token,
+            "scope": SCOPE,
+            "expires_in": ACCESS_TOKEN_LIFETIME,
+            "token_type": "Bearer",
+        });
+
+        if include_refresh_token {
+            let refresh_token = random_id();
+            self.refresh_tokens.insert(refresh_token.clone());
+
+            response["refresh_token"] = Value::String(refresh_token);
+        }
+
+        MockResponse::Json(200, response)
+    }
+}
+
+/// Returns the page of items requested with `$top` and `$skip`.
+pub fn paginate(items: Vec<Value>, url: &Url) -> MockResponse {
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+    };
+
+    let page_size = param("$top").unwrap_or(DEFAULT_PAGE_SIZE);
+    let skip = param("$skip").unwrap_or(0);
+    let total_count = items.len();
+
+    let data: Vec<Value> =
+        items.into_iter().skip(skip).take(page_size).collect();
+
+    MockResponse::Json(
+        200,
+        json!({
+            "data": data,
+            "total_count": total_count,
+            "page_size": page_size,
+        }),
+    )
+}
+
+/// Token requests are form encoded, except for device flow polling which
+/// sends JSON.
+fn parse_fields(body: &str) -> HashMap<String, String> {
+    if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(body) {
+        return map
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value.as_str()?.to_string())))
+            .collect();
+    }
+
+    // Parsing the body as the query of a URL decodes it the same way as a
+    // form.
+    Url::parse(&format!("http://localhost/?{}", body))
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+fn id_of(value: &Value) -> &str {
+    value["id"].as_str().unwrap_or_default()
+}
+
+fn random_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+/// Adds the minimum supported CLI version, when the fixture has one, so that
+/// it is sent with every response.
+fn with_min_version<R: Read>(
+    mut response: Response<R>,
+    min_version_header: Option<Header>,
+) -> Response<R> {
+    if let Some(min_version_header) = min_version_header {
+        response.add_header(min_version_header);
+    }
+
+    response
+}
+
+fn error_response(status: u16, message: &str) -> MockResponse {
+    MockResponse::Json(status, json!({ "message": message }))
+}
+
+fn validation_response(field: &str, message: &str) -> MockResponse {
+    MockResponse::Json(
+        422,
+        json!({
+            "message": "Validation failed.",
+            "errors": [{ "field": field, "message": message }],
+        }),
+    )
+}
+
+fn oauth_error(status: u16, error: &str) -> MockResponse {
+    MockResponse::Json(status, json!({ "error": error }))
+}
+
+fn default_fixture() -> Fixture {
+    Fixture {
+        user: json!({
+            "id": "mock-user",
+            "name": "Mock User",
+            "email": "mock@example.com",
+        }),
+        tenants: vec![json!({
+            "id": "mock-tenant",
+            "name": "Mock Workspace",
+            "subdomain": "mock",
+            "logo": null,
+            "domains": [{
+                "id": "mock-domain",
+                "url": "https://mock.parra.io",
+                "host": "mock.parra.io",
+                "name": "mock",
+                "type": "subdomain",
+            }],
+        })],
+        applications: vec![json!({
+            "id": "mock-application",
+            "name": "Mock App",
+            "description": null,
+            "type": "ios",
+            "tenant_id": "mock-tenant",
+            "ios": { "bundle_id": "com.example.mock" },
+        })],
+        device_flow: DeviceFlowFixture::default(),
+        min_cli_version: None,
+    }
+}
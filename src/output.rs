@@ -0,0 +1,154 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::io::{self, ErrorKind, Write};
+use std::process::exit;
+use std::sync::OnceLock;
+
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// How command results are written to stdout. Messages about progress are
+/// always written to stderr, so that the results can be piped to other tools.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+/// How a value is displayed when the output format is a table. Lists use a
+/// column for each header, and single values a line for each header.
+pub trait Tabular {
+    fn headers() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+}
+
+pub fn init_output_format(format: OutputFormat) {
+    OUTPUT_FORMAT.get_or_init(|| format);
+}
+
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Whether results are being written in a machine readable format.
+pub fn is_structured() -> bool {
+    output_format() != OutputFormat::Table
+}
+
+pub fn print_list<T: Serialize + Tabular>(
+    items: &[T],
+) -> Result<(), Box<dyn Error>> {
+    match output_format() {
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = items.iter().map(T::row).collect();
+
+            print_table(&T::headers(), &rows)
+        }
+        _ => print_structured(&items),
+    }
+}
+
+pub fn print_item<T: Serialize + Tabular>(
+    item: &T,
+) -> Result<(), Box<dyn Error>> {
+    match output_format() {
+        OutputFormat::Table => {
+            let headers = T::headers();
+            let width = headers.iter().map(|header| header.len()).max();
+
+            let lines: Vec<String> = headers
+                .iter()
+                .zip(item.row())
+                .map(|(header, value)| {
+                    format!(
+                        "{:width$}  {}",
+                        format!("{}:", header),
+                        value,
+                        width = width.unwrap_or_default() + 1
+                    )
+                })
+                .collect();
+
+            write_lines(&lines)
+        }
+        _ => print_structured(item),
+    }
+}
+
+/// Prints the value as JSON or YAML. Used directly for values that are never
+/// displayed as a table.
+pub fn print_structured<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<(), Box<dyn Error>> {
+    let text = match output_format() {
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        _ => format!("{}\n", serde_json::to_string_pretty(value)?),
+    };
+
+    write_stdout(&text)
+}
+
+/// Writes the lines to stdout, each followed by a newline.
+pub fn write_lines(lines: &[String]) -> Result<(), Box<dyn Error>> {
+    let text: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+
+    write_stdout(&text)
+}
+
+/// Writes to stdout. If whatever is reading the output has stopped, like
+/// `head` once it has enough lines, the command exits quietly instead of
+/// failing.
+fn write_stdout(text: &str) -> Result<(), Box<dyn Error>> {
+    let mut stdout = io::stdout().lock();
+
+    match stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+    {
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => exit(0),
+        result => Ok(result?),
+    }
+}
+
+fn print_table(
+    headers: &[&str],
+    rows: &[Vec<String>],
+) -> Result<(), Box<dyn Error>> {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(index))
+                .map(|value| value.chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let format_row = |values: Vec<String>| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:width$}", value, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![format_row(
+        headers.iter().map(|header| header.to_uppercase()).collect(),
+    )];
+    lines.extend(rows.iter().map(|row| format_row(row.clone())));
+
+    write_lines(&lines)
+}
+
+/// The value to display in a table for an optional field.
+pub fn or_dash(value: Option<&str>) -> String {
+    value.unwrap_or("-").to_string()
+}
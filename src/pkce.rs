@@ -0,0 +1,213 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Request, Response, Server};
+
+const CALLBACK_PATH: &str = "/callback";
+
+/// A PKCE code verifier and its S256 challenge, as described in RFC 7636.
+pub struct PkceChallenge {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+pub fn generate_pkce_challenge() -> PkceChallenge {
+    let verifier = random_url_safe_string(64);
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    PkceChallenge {
+        verifier,
+        challenge,
+    }
+}
+
+pub fn generate_state() -> String {
+    random_url_safe_string(16)
+}
+
+fn random_url_safe_string(byte_count: usize) -> String {
+    let mut bytes = vec![0u8; byte_count];
+    rand::rng().fill_bytes(&mut bytes);
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// A temporary HTTP server on localhost that receives the redirect from the
+/// authorization server at the end of the authorization code flow.
+pub struct LoopbackListener {
+    server: Server,
+    pub redirect_uri: String,
+}
+
+impl LoopbackListener {
+    /// Binds to a random available port on the loopback interface.
+    pub fn bind() -> Result<Self, Box<dyn Error>> {
+        let server = Server::http("127.0.0.1:0").map_err(|error| {
+            format!("Failed to start the login callback listener: {}", error)
+        })?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .ok_or("Failed to start the login callback listener")?
+            .port();
+
+        Ok(LoopbackListener {
+            server,
+            redirect_uri: format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH),
+        })
+    }
+
+    /// Waits for the authorization server to redirect back with an
+    /// authorization code, and returns it once the `state` has been verified.
+    /// Requests to other paths, or with a different `state`, are ignored.
+    pub async fn wait_for_code(
+        self,
+        expected_state: String,
+        timeout: Duration,
+    ) -> Result<String, Box<dyn Error>> {
+        let result = tokio::task::spawn_blocking(move || {
+            self.receive_code(&expected_state, timeout)
+        })
+        .await?;
+
+        result.map_err(|error| error.into())
+    }
+
+    fn receive_code(
+        &self,
+        expected_state: &str,
+        timeout: Duration,
+    ) -> Result<String, String> {
+        // Requests for other paths, like a favicon, don't extend the time
+        // allowed for the login.
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let request = match self.server.recv_timeout(remaining) {
+                Ok(Some(request)) => request,
+                Ok(None) => {
+                    return Err("Timed out waiting for the login to complete in the browser. Try again.".to_string());
+                }
+                Err(error) => return Err(error.to_string()),
+            };
+
+            // The request URL is only a path and query, so it needs a base to
+            // be parsed.
+            let url = Url::parse("http://127.0.0.1")
+                .and_then(|base| base.join(request.url()))
+                .map_err(|error| error.to_string())?;
+
+            if url.path() != CALLBACK_PATH {
+                let _ = request.respond(Response::empty(404));
+                continue;
+            }
+
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.to_string())
+            };
+
+            // Anything can send a request to the listener, so one without the
+            // state of this login is turned away rather than ending it.
+            if param("state").as_deref() != Some(expected_state) {
+                respond_with_message(
+                    request,
+                    400,
+                    "The login callback contained an unexpected state.",
+                );
+                continue;
+            }
+
+            let result = if let Some(error) = param("error") {
+                let description = param("error_description").unwrap_or(error);
+                Err(format!("Login failed: {}", description))
+            } else {
+                param("code").ok_or(
+                    "The login callback did not include an authorization code."
+                        .to_string(),
+                )
+            };
+
+            let message = match &result {
+                Ok(_) => "Login complete. You can close this window and return to the terminal.".to_string(),
+                Err(error) => error.clone(),
+            };
+
+            respond_with_message(request, 200, &message);
+
+            return result;
+        }
+    }
+}
+
+/// Responds to the browser with a page containing the message.
+fn respond_with_message(request: Request, status: u16, message: &str) {
+    // The message can include the error description from the query, so it
+    // needs to be escaped before being included in the page.
+    let message = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    let _ = request.respond(
+        Response::from_string(format!(
+            "<html><body><p>{}</p></body></html>",
+            message
+        ))
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "text/html").unwrap()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoopbackListener;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn ignores_callbacks_with_another_state() {
+        let listener = LoopbackListener::bind().unwrap();
+        let redirect_uri = listener.redirect_uri.clone();
+        let code = tokio::task::spawn_blocking(move || {
+            listener.receive_code("state", Duration::from_secs(10))
+        });
+
+        let client = reqwest::Client::new();
+        let callback = |query: &'static str| {
+            client.get(format!("{}?{}", redirect_uri, query)).send()
+        };
+
+        let missing = callback("code=stolen").await.unwrap();
+        let mismatched = callback("code=stolen&state=other").await.unwrap();
+        let matching = callback("code=real&state=state").await.unwrap();
+
+        assert_eq!(missing.status(), 400);
+        assert_eq!(mismatched.status(), 400);
+        assert_eq!(matching.status(), 200);
+        assert_eq!(code.await.unwrap().unwrap(), "real");
+    }
+
+    #[tokio::test]
+    async fn times_out_without_a_matching_callback() {
+        let listener = LoopbackListener::bind().unwrap();
+        let redirect_uri = listener.redirect_uri.clone();
+        let code = tokio::task::spawn_blocking(move || {
+            listener.receive_code("state", Duration::from_secs(1))
+        });
+
+        let response = reqwest::get(format!("{}?code=stolen", redirect_uri))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 400);
+        let error = code.await.unwrap().unwrap_err();
+
+        assert!(error.contains("Timed out"));
+    }
+}
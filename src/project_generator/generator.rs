@@ -3,41 +3,66 @@ use std::process::{exit, Command};
 use std::{error::Error, fs};
 
 use convert_case::{Case, Casing};
-use inquire::Confirm;
 
 use crate::{
     project_generator::{renderer, templates},
     types::api::{ApplicationResponse, TenantResponse},
 };
 
-pub fn generate_xcode_project<'a>(
+/// A file rendered from one of the templates.
+pub struct RenderedFile {
+    /// Relative to the project directory.
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// The directories and files of a project, rendered from the templates without
+/// writing anything.
+pub struct RenderedProject {
+    /// The directory containing the app's sources, relative to the project
+    /// directory.
+    pub target_dir: PathBuf,
+    /// Relative to the project directory.
+    pub directories: Vec<PathBuf>,
+    pub files: Vec<RenderedFile>,
+    /// The spec that xcodegen generates the Xcode project from.
+    pub project_yaml: String,
+}
+
+/// Where the xcodegen spec is written while the Xcode project is generated.
+pub const XCODEGEN_SPEC_PATH: &str = "/tmp/parra_project.yml";
+
+pub fn generate_xcode_project(
     project_dir: &PathBuf,
     tenant: TenantResponse,
     application: ApplicationResponse,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let app_name = application.name;
-    let camel_name = app_name.to_case(Case::UpperCamel);
-    let bundle_id = application.ios.unwrap().bundle_id;
-
-    let target_dir = project_dir.join(app_name.clone());
+    let project = render_project(tenant, application)?;
 
+    // Callers confirm that an existing project can be replaced before
+    // generating a new one.
     if project_dir.exists() {
-        let result =
-            Confirm::new("Project directory already exists. Overwrite?")
-                .with_help_message(
-                    "If you choose not to proceed, the program will exit.",
-                )
-                .with_default(false)
-                .prompt()?;
-
-        if !result {
-            exit(1);
-        }
-
         fs::remove_dir_all(&project_dir)?;
     }
 
-    create_project_structure(&target_dir)?;
+    write_project(project_dir, &project)?;
+
+    run_xcodegen(project_dir, &project.project_yaml)?;
+
+    install_spm_dependencies(project_dir)?;
+
+    Ok(project_dir.join(project.target_dir))
+}
+
+pub fn render_project(
+    tenant: TenantResponse,
+    application: ApplicationResponse,
+) -> Result<RenderedProject, Box<dyn Error>> {
+    let app_name = application.name;
+    let camel_name = app_name.to_case(Case::UpperCamel);
+    let bundle_id = application.ios.unwrap().bundle_id;
+
+    let target_dir = PathBuf::from(app_name.clone());
 
     let globals = liquid::object!({
         "app": {
@@ -53,7 +78,8 @@ pub fn generate_xcode_project<'a>(
         }
     });
 
-    create_project_files(tenant, &target_dir, &camel_name, &globals)?;
+    let files =
+        create_project_files(tenant, &target_dir, &camel_name, &globals)?;
 
     let project_yaml = renderer::render_template(
         &templates::get_project_yaml_template(),
@@ -61,37 +87,56 @@ pub fn generate_xcode_project<'a>(
     )
     .unwrap();
 
-    run_xcodegen(&project_dir, &project_yaml)?;
+    Ok(RenderedProject {
+        directories: create_project_structure(&target_dir),
+        target_dir,
+        files,
+        project_yaml,
+    })
+}
 
-    install_spm_dependencies(&project_dir)?;
+/// Creates the directories and writes the files of the project in the
+/// directory, without generating the Xcode project.
+pub fn write_project(
+    project_dir: &Path,
+    project: &RenderedProject,
+) -> Result<(), Box<dyn Error>> {
+    for directory in &project.directories {
+        fs::create_dir_all(project_dir.join(directory))?;
+    }
 
-    Ok(target_dir)
+    for file in &project.files {
+        fs::write(project_dir.join(&file.path), &file.contents)?;
+    }
+
+    Ok(())
 }
 
-fn create_project_structure(
-    target_path: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
-    fs::create_dir_all(target_path)?;
+/// The commands that are run to generate the Xcode project once its files are
+/// written.
+pub fn generation_commands(project_dir: &Path) -> Vec<Command> {
+    vec![
+        xcodegen_command(project_dir),
+        resolve_package_dependencies_command(project_dir),
+    ]
+}
 
-    fs::create_dir_all(target_path.join("Assets.xcassets"))?;
-    fs::create_dir_all(
+fn create_project_structure(target_path: &Path) -> Vec<PathBuf> {
+    vec![
+        target_path.to_path_buf(),
+        target_path.join("Assets.xcassets"),
         target_path.join("Assets.xcassets/AccentColor.colorset"),
-    )?;
-    fs::create_dir_all(target_path.join("Assets.xcassets/AppIcon.appiconset"))?;
-
-    fs::create_dir_all(
+        target_path.join("Assets.xcassets/AppIcon.appiconset"),
         target_path.join("Preview Content/Preview Assets.xcassets"),
-    )?;
-
-    Ok(())
+    ]
 }
 
 fn create_project_files(
     tenant: TenantResponse,
-    target_path: &PathBuf,
+    target_path: &Path,
     camel_app_name: &str,
     globals: &liquid::Object,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<RenderedFile>, Box<dyn Error>> {
     let app_swift_yaml = renderer::render_template(
         &templates::get_app_swift_template(),
         &globals,
@@ -104,8 +149,8 @@ fn create_project_files(
     )
     .unwrap();
 
-    create_entitlements_files(tenant, &target_path)?;
-    create_asset_catalog(&target_path, &globals)?;
+    let mut files = create_entitlements_files(tenant, target_path)?;
+    files.extend(create_asset_catalog(target_path, globals)?);
 
     let preview_assets_json =
         renderer::render_template(&templates::get_assets_json(), &globals)
@@ -116,17 +161,26 @@ fn create_project_files(
     let preview_assets_path = target_path
         .join("Preview Content/Preview Assets.xcassets/Contents.json");
 
-    fs::write(app_path, app_swift_yaml)?;
-    fs::write(content_view_path, app_content_view_yaml)?;
-    fs::write(preview_assets_path, preview_assets_json)?;
+    files.push(RenderedFile {
+        path: app_path,
+        contents: app_swift_yaml,
+    });
+    files.push(RenderedFile {
+        path: content_view_path,
+        contents: app_content_view_yaml,
+    });
+    files.push(RenderedFile {
+        path: preview_assets_path,
+        contents: preview_assets_json,
+    });
 
-    Ok(())
+    Ok(files)
 }
 
 fn create_entitlements_files(
     tenant: TenantResponse,
-    target_path: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
+    target_path: &Path,
+) -> Result<Vec<RenderedFile>, Box<dyn Error>> {
     // Put the domains in order by priority that they appear in the Apple entitlements
     // file. This is done by looking at the order of the domain type enum cases.
     let mut domains = tenant.domains;
@@ -190,16 +244,22 @@ fn create_entitlements_files(
     let release_entitlements_path =
         target_path.join("Entitlements-release.entitlements");
 
-    fs::write(debug_entitlements_path, debug_entitlements_yaml)?;
-    fs::write(release_entitlements_path, release_entitlements_yaml)?;
-
-    Ok(())
+    Ok(vec![
+        RenderedFile {
+            path: debug_entitlements_path,
+            contents: debug_entitlements_yaml,
+        },
+        RenderedFile {
+            path: release_entitlements_path,
+            contents: release_entitlements_yaml,
+        },
+    ])
 }
 
 fn create_asset_catalog(
-    target_path: &PathBuf,
+    target_path: &Path,
     globals: &liquid::Object,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<RenderedFile>, Box<dyn Error>> {
     let assets_json =
         renderer::render_template(&templates::get_assets_json(), &globals)
             .unwrap();
@@ -220,28 +280,43 @@ fn create_asset_catalog(
     let app_icon_path =
         target_path.join("Assets.xcassets/AppIcon.appiconset/Contents.json");
 
-    fs::write(assets_path, assets_json)?;
-    fs::write(accent_color_path, accent_color_json)?;
-    fs::write(app_icon_path, app_icon_json)?;
+    Ok(vec![
+        RenderedFile {
+            path: assets_path,
+            contents: assets_json,
+        },
+        RenderedFile {
+            path: accent_color_path,
+            contents: accent_color_json,
+        },
+        RenderedFile {
+            path: app_icon_path,
+            contents: app_icon_json,
+        },
+    ])
+}
 
-    Ok(())
+fn xcodegen_command(project_path: &Path) -> Command {
+    let mut command = Command::new("xcodegen");
+    command
+        .arg("--spec")
+        .arg(XCODEGEN_SPEC_PATH)
+        .arg("--project")
+        .arg(project_path.to_str().unwrap())
+        .arg("--project-root")
+        .arg(project_path.to_str().unwrap());
+
+    command
 }
 
 fn run_xcodegen(
     project_path: &PathBuf,
     template: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let tmp_project_yaml_path = Path::new("/tmp/parra_project.yml");
+    let tmp_project_yaml_path = Path::new(XCODEGEN_SPEC_PATH);
     fs::write(tmp_project_yaml_path, template)?;
 
-    let result = Command::new("xcodegen")
-        .arg("--spec")
-        .arg(tmp_project_yaml_path.to_str().unwrap())
-        .arg("--project")
-        .arg(project_path.to_str().unwrap().to_owned())
-        .arg("--project-root")
-        .arg(project_path.to_str().unwrap().to_owned())
-        .output();
+    let result = xcodegen_command(project_path).output();
 
     match result {
         Ok(output) => {
@@ -262,11 +337,15 @@ fn run_xcodegen(
     }
 }
 
+fn resolve_package_dependencies_command(path: &Path) -> Command {
+    let mut command = Command::new("xcodebuild");
+    command.arg("-resolvePackageDependencies").current_dir(path);
+
+    command
+}
+
 fn install_spm_dependencies(path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    Command::new("xcodebuild")
-        .arg("-resolvePackageDependencies")
-        .current_dir(path)
-        .output()?;
+    resolve_package_dependencies_command(path).output()?;
 
     Ok(())
 }
@@ -0,0 +1,484 @@
+use inquire::validator::Validation;
+use inquire::{Confirm, Select, Text};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// A question asked by a wizard. The key identifies the question in an answers
+/// file, and the option is the command line option that also answers it, if
+/// there is one.
+pub struct Question<'a> {
+    pub key: &'static str,
+    pub message: &'a str,
+    pub help: Option<&'a str>,
+    pub option: Option<&'static str>,
+}
+
+/// A choice in a select question. Answers files can refer to it by its ID or
+/// its name, and the label is what is displayed in the terminal.
+pub struct Choice {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+}
+
+/// Checks the answer to a text question, returning why it is invalid.
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// Where the answers to a wizard's questions come from.
+pub trait Prompter {
+    fn confirm(
+        &mut self,
+        question: &Question,
+        default: bool,
+    ) -> Result<bool, Box<dyn Error>>;
+
+    /// Returns the index of the selected choice.
+    fn select(
+        &mut self,
+        question: &Question,
+        choices: &[Choice],
+    ) -> Result<usize, Box<dyn Error>>;
+
+    fn text(
+        &mut self,
+        question: &Question,
+        default: Option<&str>,
+        validator: Validator,
+    ) -> Result<String, Box<dyn Error>>;
+}
+
+/// Asks each question in the terminal.
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn confirm(
+        &mut self,
+        question: &Question,
+        default: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        let mut prompt = Confirm::new(question.message).with_default(default);
+
+        if let Some(help) = question.help {
+            prompt = prompt.with_help_message(help);
+        }
+
+        Ok(prompt.prompt()?)
+    }
+
+    fn select(
+        &mut self,
+        question: &Question,
+        choices: &[Choice],
+    ) -> Result<usize, Box<dyn Error>> {
+        let labels =
+            choices.iter().map(|choice| choice.label.clone()).collect();
+        let mut prompt = Select::new(question.message, labels);
+
+        if let Some(help) = question.help {
+            prompt = prompt.with_help_message(help);
+        }
+
+        Ok(prompt.raw_prompt()?.index)
+    }
+
+    fn text(
+        &mut self,
+        question: &Question,
+        default: Option<&str>,
+        validator: Validator,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut prompt =
+            Text::new(question.message).with_validator(move |input: &str| {
+                match validator(input.trim()) {
+                    Ok(()) => Ok(Validation::Valid),
+                    Err(message) => Ok(Validation::Invalid(message.into())),
+                }
+            });
+
+        if let Some(default) = default {
+            prompt = prompt.with_default(default);
+        }
+
+        if let Some(help) = question.help {
+            prompt = prompt.with_help_message(help);
+        }
+
+        Ok(prompt.prompt()?.trim().to_string())
+    }
+}
+
+/// Never asks anything. Text questions with a default use it, and any other
+/// question fails with the option or answer that has to be provided instead.
+pub struct NonInteractivePrompter;
+
+impl Prompter for NonInteractivePrompter {
+    fn confirm(
+        &mut self,
+        question: &Question,
+        _default: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        Err(unanswered(question))
+    }
+
+    fn select(
+        &mut self,
+        question: &Question,
+        _choices: &[Choice],
+    ) -> Result<usize, Box<dyn Error>> {
+        Err(unanswered(question))
+    }
+
+    fn text(
+        &mut self,
+        question: &Question,
+        default: Option<&str>,
+        validator: Validator,
+    ) -> Result<String, Box<dyn Error>> {
+        let default = default.ok_or_else(|| unanswered(question))?;
+
+        validator(default).map_err(|message| {
+            format!(
+                "The default answer to \"{}\" is invalid: {} {}",
+                question.message,
+                message,
+                how_to_answer(question)
+            )
+        })?;
+
+        Ok(default.to_string())
+    }
+}
+
+fn unanswered(question: &Question) -> Box<dyn Error> {
+    format!(
+        "No answer was provided to \"{}\". {}",
+        question.message,
+        how_to_answer(question)
+    )
+    .into()
+}
+
+fn how_to_answer(question: &Question) -> String {
+    match question.option {
+        Some(option) => format!(
+            "Pass {}, or set `{}` in the answers file.",
+            option, question.key
+        ),
+        None => format!("Set `{}` in the answers file.", question.key),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Answer {
+    Bool(bool),
+    Text(String),
+}
+
+/// The answers from a JSON or YAML file, keyed by question.
+pub struct Answers {
+    values: BTreeMap<String, Answer>,
+}
+
+impl Answers {
+    /// Reads the answers from the file, which is parsed as JSON if it has a
+    /// `.json` extension and as YAML otherwise. Keys that don't belong to one
+    /// of the questions are rejected, so that typos don't go unnoticed.
+    pub fn load(path: &Path, keys: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read_to_string(path).map_err(|error| {
+            format!("Failed to read answers {}: {}", path.display(), error)
+        })?;
+
+        let values: BTreeMap<String, Answer> =
+            if path.extension().is_some_and(|ext| ext == "json") {
+                serde_json::from_str(&data).map_err(|error| error.to_string())
+            } else {
+                serde_yaml::from_str(&data).map_err(|error| error.to_string())
+            }
+            .map_err(|error| {
+                format!("Invalid answers file {}: {}", path.display(), error)
+            })?;
+
+        if let Some(key) =
+            values.keys().find(|key| !keys.contains(&key.as_str()))
+        {
+            return Err(format!(
+                "Unknown question `{}` in {}. Expected one of: {}.",
+                key,
+                path.display(),
+                keys.join(", ")
+            )
+            .into());
+        }
+
+        Ok(Answers { values })
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// The answer to a yes or no question, if the file has one.
+    pub fn flag(&self, key: &str) -> Option<bool> {
+        match self.values.get(key) {
+            Some(Answer::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Answers a yes or no question that the file doesn't answer itself.
+    pub fn imply(&mut self, key: &str, value: bool) {
+        self.values
+            .entry(key.to_string())
+            .or_insert(Answer::Bool(value));
+    }
+
+    /// The answer to a text question, if the file has one.
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(Answer::Text(value)) => Some(value.trim()),
+            _ => None,
+        }
+    }
+}
+
+/// Answers questions from an answers file, and asks the fallback any that it
+/// doesn't answer.
+pub struct AnswersPrompter {
+    answers: Answers,
+    fallback: Box<dyn Prompter>,
+}
+
+impl AnswersPrompter {
+    pub fn new(answers: Answers, fallback: Box<dyn Prompter>) -> Self {
+        AnswersPrompter { answers, fallback }
+    }
+}
+
+impl Prompter for AnswersPrompter {
+    fn confirm(
+        &mut self,
+        question: &Question,
+        default: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        match self.answers.values.get(question.key) {
+            Some(Answer::Bool(value)) => Ok(*value),
+            Some(Answer::Text(_)) => Err(format!(
+                "The answer to `{}` must be true or false.",
+                question.key
+            )
+            .into()),
+            None => self.fallback.confirm(question, default),
+        }
+    }
+
+    fn select(
+        &mut self,
+        question: &Question,
+        choices: &[Choice],
+    ) -> Result<usize, Box<dyn Error>> {
+        let value = match self.answers.values.get(question.key) {
+            Some(Answer::Text(value)) => value.trim(),
+            Some(Answer::Bool(_)) => {
+                return Err(format!(
+                    "The answer to `{}` must be a name or ID.",
+                    question.key
+                )
+                .into())
+            }
+            None => return self.fallback.select(question, choices),
+        };
+
+        choices
+            .iter()
+            .position(|choice| choice.id == value || choice.name == value)
+            .ok_or_else(|| {
+                let names: Vec<&str> =
+                    choices.iter().map(|choice| choice.name.as_str()).collect();
+
+                format!(
+                    "The answer to `{}` doesn't match any of: {}.",
+                    question.key,
+                    names.join(", ")
+                )
+                .into()
+            })
+    }
+
+    fn text(
+        &mut self,
+        question: &Question,
+        default: Option<&str>,
+        validator: Validator,
+    ) -> Result<String, Box<dyn Error>> {
+        let value = match self.answers.values.get(question.key) {
+            Some(Answer::Text(value)) => value.trim(),
+            Some(Answer::Bool(_)) => {
+                return Err(format!(
+                    "The answer to `{}` must be text.",
+                    question.key
+                )
+                .into())
+            }
+            None => return self.fallback.text(question, default, validator),
+        };
+
+        validator(value).map_err(|message| {
+            format!("Invalid answer to `{}`: {}", question.key, message)
+        })?;
+
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const KEYS: [&str; 3] =
+        ["use_existing_workspace", "workspace", "overwrite"];
+
+    fn write_answers(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = env::temp_dir().join(format!(
+            "parra-answers-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    fn question(key: &'static str) -> Question<'static> {
+        Question {
+            key,
+            message: key,
+            help: None,
+            option: None,
+        }
+    }
+
+    fn choices() -> Vec<Choice> {
+        ["Acme", "Globex"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Choice {
+                id: format!("tenant-{}", index + 1),
+                name: name.to_string(),
+                label: name.to_string(),
+            })
+            .collect()
+    }
+
+    /// Answers every question with a fixed value.
+    struct FixedPrompter;
+
+    impl Prompter for FixedPrompter {
+        fn confirm(
+            &mut self,
+            _question: &Question,
+            _default: bool,
+        ) -> Result<bool, Box<dyn Error>> {
+            Ok(true)
+        }
+
+        fn select(
+            &mut self,
+            _question: &Question,
+            _choices: &[Choice],
+        ) -> Result<usize, Box<dyn Error>> {
+            Ok(1)
+        }
+
+        fn text(
+            &mut self,
+            _question: &Question,
+            _default: Option<&str>,
+            _validator: Validator,
+        ) -> Result<String, Box<dyn Error>> {
+            Ok("fallback".to_string())
+        }
+    }
+
+    #[test]
+    fn loads_json_and_yaml_answers() {
+        let json = write_answers(
+            "answers.json",
+            r#"{ "use_existing_workspace": true, "workspace": "Acme" }"#,
+        );
+        let yaml = write_answers(
+            "answers.yml",
+            "use_existing_workspace: true\nworkspace: Acme\n",
+        );
+
+        for path in [json, yaml] {
+            let answers = Answers::load(&path, &KEYS).unwrap();
+
+            assert_eq!(answers.flag("use_existing_workspace"), Some(true));
+            assert_eq!(answers.text("workspace"), Some("Acme"));
+            assert!(!answers.contains("overwrite"));
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_answers() {
+        let path =
+            write_answers("unknown.yml", "workspace: Acme\nwrokspace: Acme\n");
+
+        let error = Answers::load(&path, &KEYS).err().unwrap();
+
+        assert!(error.to_string().contains("Unknown question `wrokspace`"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn answers_prompter_falls_back_to_unanswered_questions() {
+        let path = write_answers("fallback.yml", "workspace: tenant-1\n");
+        let answers = Answers::load(&path, &KEYS).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let mut prompter =
+            AnswersPrompter::new(answers, Box::new(FixedPrompter));
+
+        let index =
+            prompter.select(&question("workspace"), &choices()).unwrap();
+        assert_eq!(index, 0);
+
+        let overwrite =
+            prompter.confirm(&question("overwrite"), false).unwrap();
+        assert!(overwrite);
+
+        let text = prompter
+            .text(&question("project_path"), None, |_| Ok(()))
+            .unwrap();
+        assert_eq!(text, "fallback");
+    }
+
+    #[test]
+    fn answers_prompter_fails_without_a_fallback_answer() {
+        let path = write_answers("non-interactive.yml", "workspace: Acme\n");
+        let answers = Answers::load(&path, &KEYS).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let mut prompter =
+            AnswersPrompter::new(answers, Box::new(NonInteractivePrompter));
+
+        assert_eq!(
+            prompter.select(&question("workspace"), &choices()).unwrap(),
+            0
+        );
+
+        let error = prompter
+            .confirm(&question("use_existing_workspace"), true)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("`use_existing_workspace`"));
+    }
+}
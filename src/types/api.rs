@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use super::auth::Credental;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UserResponse {
     pub id: String,
     pub name: String,
@@ -14,7 +14,7 @@ pub struct UserInfoResponse {
     pub user: UserResponse,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AuthorizedUser {
     pub credential: Credental,
     pub user: UserResponse,
@@ -26,13 +26,13 @@ pub struct TenantRequest {
     pub is_test: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Size {
     pub width: u32,
     pub height: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TenantLogo {
     pub id: String,
     pub url: String,
@@ -53,7 +53,7 @@ pub enum TenantDomainType {
     Fallback,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TenantDomain {
     pub id: String,
     pub url: String,
@@ -63,10 +63,14 @@ pub struct TenantDomain {
     pub domain_type: TenantDomainType,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TenantResponse {
     pub id: String,
     pub name: String,
+    /// Test workspaces are excluded from billing and can be used to try out
+    /// Parra.
+    #[serde(default)]
+    pub is_test: bool,
     pub subdomain: Option<String>,
     pub logo: Option<TenantLogo>,
     pub domains: Vec<TenantDomain>,
@@ -89,12 +93,23 @@ pub struct ApplicationRequest {
     pub is_new_project: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Only the fields that are provided are changed.
+#[derive(Debug, Serialize)]
+pub struct UpdateApplicationRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ios_bundle_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ApplicationIosConfig {
     pub bundle_id: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ApplicationResponse {
     pub id: String,
     pub name: String,
@@ -105,7 +120,36 @@ pub struct ApplicationResponse {
     pub ios: Option<ApplicationIosConfig>,
 }
 
+/// A page of a collection returned from a list endpoint.
 #[derive(Debug, Deserialize)]
-pub struct ApplicationCollectionResponse {
-    pub data: Vec<ApplicationResponse>,
+pub struct CollectionResponse<T> {
+    pub data: Vec<T>,
+    /// The number of items in the whole collection, across every page.
+    pub total_count: Option<u64>,
+    pub page_size: Option<u64>,
+    /// Present when there are more pages and the endpoint uses cursors instead
+    /// of offsets to fetch them.
+    pub next_cursor: Option<String>,
+}
+
+/// Some list endpoints return every item as a bare array instead of a page.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum CollectionPage<T> {
+    Page(CollectionResponse<T>),
+    All(Vec<T>),
+}
+
+impl<T> CollectionPage<T> {
+    pub fn into_response(self) -> CollectionResponse<T> {
+        match self {
+            CollectionPage::Page(response) => response,
+            CollectionPage::All(data) => CollectionResponse {
+                total_count: Some(data.len() as u64),
+                page_size: None,
+                next_cursor: None,
+                data,
+            },
+        }
+    }
 }
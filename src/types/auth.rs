@@ -5,6 +5,10 @@ pub struct Credental {
     pub token: String,
     pub refresh_token: String,
     pub expiry: u64,
+    /// The space separated scopes granted to the token. Missing for
+    /// credentials stored by older versions and for tokens provided directly.
+    #[serde(default)]
+    pub scope: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -19,11 +23,24 @@ pub struct AuthResponse {
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct RefreshResponse {
     pub access_token: String,
+    /// Only present when the server rotates refresh tokens. The refresh token
+    /// that was used is no longer valid when this is provided.
+    pub refresh_token: Option<String>,
     pub scope: String,
     pub expires_in: u64,
     pub token_type: String,
 }
 
+/// Response to a client credentials grant. These never include a refresh
+/// token, since the client can always request a new access token.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct ClientCredentialsResponse {
+    pub access_token: String,
+    pub scope: Option<String>,
+    pub expires_in: u64,
+    pub token_type: String,
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct DeviceAuthResponse {
     /// Do not show this to the user. It is used to poll for the token.
@@ -43,3 +60,25 @@ pub struct TokenRequest {
     pub device_code: String,
     pub client_id: String,
 }
+
+/// Error codes returned by the token endpoint. The device flow specific ones
+/// are described in RFC 8628, section 3.5.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub enum OAuthError {
+    #[serde(rename = "authorization_pending")]
+    AuthorizationPending,
+    #[serde(rename = "slow_down")]
+    SlowDown,
+    #[serde(rename = "access_denied")]
+    AccessDenied,
+    #[serde(rename = "expired_token")]
+    ExpiredToken,
+    #[serde(untagged)]
+    Other(String),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct OAuthErrorResponse {
+    pub error: OAuthError,
+    pub error_description: Option<String>,
+}
@@ -0,0 +1,30 @@
+use regex::Regex;
+
+const BUNDLE_ID_PATTERN: &str = r"^[a-zA-Z0-9-]+(\.[a-zA-Z0-9-]+){2,}$";
+
+/// Checks that the bundle ID is in the format Apple requires. Returns a
+/// message describing the problem if it isn't.
+pub fn validate_bundle_id(bundle_id: &str) -> Result<(), String> {
+    // The shortest valid bundle ID is x.y.z
+    if bundle_id.len() < 5 || bundle_id.len() > 155 {
+        return Err("The bundle ID must be between 5 and 155 characters long."
+            .to_string());
+    }
+
+    let re = Regex::new(BUNDLE_ID_PATTERN).unwrap();
+
+    if re.is_match(bundle_id) {
+        Ok(())
+    } else {
+        Err("The bundle ID string must contain only alphanumeric characters (A–Z, a–z, and 0–9), hyphens (-), and periods (.). Typically, you use a reverse-DNS format for bundle ID strings. Bundle IDs are case-insensitive.".to_string())
+    }
+}
+
+/// Checks that a required value isn't blank.
+pub fn validate_present(value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err("A value is required.".to_string())
+    } else {
+        Ok(())
+    }
+}